@@ -0,0 +1,197 @@
+//! Content negotiation for the request body of `#[derive(Mapping)]` extractors.
+
+use std::{fmt, str::FromStr};
+
+use bytes::Bytes;
+use volo_http::{
+    body::Body,
+    context::ServerContext,
+    http::{
+        header::{CONTENT_TYPE, HeaderMap},
+        request::Parts,
+    },
+    server::extract::FromRequest,
+};
+
+use crate::{
+    multipart::{MultipartLimits, MultipartReader},
+    rejection::MappingRejection,
+};
+
+/// A body encoding understood by the generated extractors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BodyKind {
+    /// `application/json` or any `application/*+json`.
+    Json,
+    /// `application/x-www-form-urlencoded`.
+    Form,
+    /// `multipart/form-data`.
+    Multipart,
+    /// `application/x-protobuf`, `application/protobuf` or `application/vnd.google.protobuf`.
+    Protobuf,
+    /// A [`BodyCodec`](crate::codec::BodyCodec), matched against its media types.
+    Codec {
+        name: &'static str,
+        media_types: &'static [&'static str],
+        suffix: Option<&'static str>,
+    },
+}
+
+impl BodyKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Form => "form",
+            Self::Multipart => "multipart",
+            Self::Protobuf => "protobuf",
+            Self::Codec { name, .. } => name,
+        }
+    }
+
+    /// The media type advertised for this kind in rejections.
+    pub fn essence(&self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::Form => "application/x-www-form-urlencoded",
+            Self::Multipart => "multipart/form-data",
+            Self::Protobuf => "application/x-protobuf",
+            Self::Codec {
+                name, media_types, ..
+            } => media_types.first().unwrap_or(name),
+        }
+    }
+
+    fn from_mime(mime: &mime::Mime, accept: &[BodyKind]) -> Option<Self> {
+        let codec = || {
+            accept.iter().copied().find(|kind| match kind {
+                Self::Codec {
+                    media_types,
+                    suffix,
+                    ..
+                } => {
+                    media_types.contains(&mime.essence_str())
+                        || suffix.is_some_and(|suffix| {
+                            mime.suffix().is_some_and(|s| s.as_str() == suffix)
+                        })
+                }
+                _ => false,
+            })
+        };
+        match (mime.type_(), mime.subtype(), mime.suffix()) {
+            (mime::APPLICATION, mime::JSON, _) | (mime::APPLICATION, _, Some(mime::JSON)) => {
+                Some(Self::Json)
+            }
+            (mime::APPLICATION, mime::WWW_FORM_URLENCODED, _) => Some(Self::Form),
+            (mime::MULTIPART, mime::FORM_DATA, _) => Some(Self::Multipart),
+            (mime::APPLICATION, subtype, _)
+                if ["x-protobuf", "protobuf", "vnd.google.protobuf"]
+                    .contains(&subtype.as_str()) =>
+            {
+                Some(Self::Protobuf)
+            }
+            _ => codec(),
+        }
+    }
+}
+
+impl fmt::Display for BodyKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Pick the body kind from `Content-Type`, restricted to `accept`.
+///
+/// Returns `None` when the request carries no `Content-Type`. A kind outside `accept` is rejected
+/// with `415 Unsupported Media Type`. Bodies are only decoded as UTF-8, so any other `charset`
+/// parameter is rejected with the same status and an `unsupported_charset` code. `utf-8` and its
+/// `utf8` alias are accepted in any case, quoted or not.
+pub fn negotiate(
+    headers: &HeaderMap,
+    accept: &'static [BodyKind],
+) -> Result<Option<BodyKind>, MappingRejection> {
+    let Some(content_type) = headers.get(CONTENT_TYPE) else {
+        return Ok(None);
+    };
+    let unsupported = || {
+        MappingRejection::unsupported_media_type(
+            Some(String::from_utf8_lossy(content_type.as_bytes()).into_owned()),
+            accept,
+        )
+    };
+    let mime = content_type
+        .to_str()
+        .ok()
+        .and_then(|v| mime::Mime::from_str(v).ok())
+        .ok_or_else(unsupported)?;
+    let kind = BodyKind::from_mime(&mime, accept)
+        .filter(|kind| accept.contains(kind))
+        .ok_or_else(unsupported)?;
+    if let Some(charset) = mime.get_param(mime::CHARSET)
+        && !is_utf8(charset.as_str())
+    {
+        return Err(MappingRejection::unsupported_charset(
+            String::from_utf8_lossy(content_type.as_bytes()).into_owned(),
+            charset.to_string(),
+        ));
+    }
+    Ok(Some(kind))
+}
+
+fn is_utf8(charset: &str) -> bool {
+    let charset = charset.trim_matches('"');
+    charset.eq_ignore_ascii_case("utf-8") || charset.eq_ignore_ascii_case("utf8")
+}
+
+/// The negotiated request body, read once and shared by every body source.
+pub struct RequestBody {
+    kind: Option<BodyKind>,
+    bytes: Bytes,
+    multipart: Option<MultipartReader>,
+}
+
+impl RequestBody {
+    /// Negotiate the body kind and read the body accordingly.
+    ///
+    /// Multipart bodies are left unread and streamed part by part; everything else is collected
+    /// into bytes. A non-empty body without `Content-Type` is rejected with
+    /// `415 Unsupported Media Type`.
+    pub async fn read(
+        cx: &mut ServerContext,
+        parts: &Parts,
+        body: Body,
+        accept: &'static [BodyKind],
+        limits: &MultipartLimits,
+    ) -> Result<Self, MappingRejection> {
+        let kind = negotiate(&parts.headers, accept)?;
+        if kind == Some(BodyKind::Multipart) {
+            return Ok(Self {
+                kind,
+                bytes: Bytes::new(),
+                multipart: Some(MultipartReader::new(&parts.headers, body, limits)?),
+            });
+        }
+        let bytes = Bytes::from_request(cx, parts.clone(), body).await?;
+        if kind.is_none() && !bytes.is_empty() {
+            return Err(MappingRejection::unsupported_media_type(None, accept));
+        }
+        Ok(Self {
+            kind,
+            bytes,
+            multipart: None,
+        })
+    }
+
+    pub fn kind(&self) -> Option<BodyKind> {
+        self.kind
+    }
+
+    /// The collected body, empty for multipart requests.
+    pub fn bytes(&self) -> &Bytes {
+        &self.bytes
+    }
+
+    pub fn into_multipart(self) -> Option<MultipartReader> {
+        self.multipart
+    }
+}
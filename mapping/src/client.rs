@@ -0,0 +1,477 @@
+//! Client-side counterpart of the extractors, for structs marked `#[mapping(client)]`.
+//!
+//! The derive writes each field back to where the server reads it from: `#[header]` fields become
+//! headers, `#[cookie]` fields one `Cookie` header, `#[query]` fields the query string, `#[uri]`
+//! fields the placeholders of a route template such as `/test/{pid}/{cid}`, and `#[json]` or
+//! `#[form]` fields the body. `#[ext]` fields only exist on the server and are skipped.
+
+use std::{error::Error, fmt, fmt::Display};
+
+use serde::{
+    Serialize,
+    ser::{self, Impossible},
+};
+use volo_http::{
+    body::Body,
+    error::BoxError,
+    http::{
+        HeaderMap, HeaderName, HeaderValue, Method, Uri,
+        header::{CONTENT_TYPE, COOKIE},
+    },
+    request::Request,
+};
+
+use crate::{query::QueryStyle, rejection::Source};
+
+/// Build an outgoing request from `Self`.
+///
+/// `#[derive(Mapping)]` implements this for structs marked `#[mapping(client)]`, so the same
+/// struct a handler extracts can be sent by its callers.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` cannot be written to a request",
+    note = "mark the struct with `#[derive(Mapping)]` and `#[mapping(client)]`"
+)]
+pub trait IntoRequest: Sized {
+    /// Write every field into `writer`; flattened fields write into their parent's writer.
+    fn write_to(self, writer: &mut RequestWriter) -> Result<(), RequestError>;
+
+    /// Build a `method` request for the route `template`, whose `{name}` placeholders are filled
+    /// by `#[uri]` fields. The template may carry a scheme and authority.
+    fn into_request(self, method: Method, template: &str) -> Result<Request, RequestError> {
+        let mut writer = RequestWriter::default();
+        self.write_to(&mut writer)?;
+        writer.finish(method, template)
+    }
+}
+
+/// The parts of a request collected from a struct's fields.
+#[derive(Debug, Default)]
+pub struct RequestWriter {
+    params: Vec<(&'static str, String)>,
+    query: Vec<(String, String)>,
+    headers: HeaderMap,
+    cookies: Vec<String>,
+    body: Option<(&'static str, Vec<u8>)>,
+}
+
+impl RequestWriter {
+    /// Append a header; collection fields call this once per value.
+    pub fn header<T: Display + ?Sized>(
+        &mut self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<(), RequestError> {
+        let header_name = HeaderName::try_from(name)
+            .map_err(|e| RequestError::invalid(Source::Header, name, e))?;
+        let value = HeaderValue::try_from(value.to_string())
+            .map_err(|e| RequestError::invalid(Source::Header, name, e))?;
+        self.headers.append(header_name, value);
+        Ok(())
+    }
+
+    /// Fill the `{name}` placeholder of the route template.
+    pub fn param<T: Display + ?Sized>(&mut self, name: &'static str, value: &T) {
+        self.params.push((name, value.to_string()));
+    }
+
+    /// Add a `name=value` pair to the `Cookie` header.
+    pub fn cookie<T: Display + ?Sized>(
+        &mut self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<(), RequestError> {
+        let value = value.to_string();
+        if value.contains([';', '"']) || value.chars().any(char::is_control) {
+            return Err(RequestError::invalid(
+                Source::Cookie,
+                name,
+                "cookie values cannot contain `;`, `\"` or control characters",
+            ));
+        }
+        self.cookies.push(format!("{name}={value}"));
+        Ok(())
+    }
+
+    /// Add a query field; `None` adds nothing and sequences follow `style`.
+    pub fn query<T: Serialize + ?Sized>(
+        &mut self,
+        name: &'static str,
+        style: QueryStyle,
+        value: &T,
+    ) -> Result<(), RequestError> {
+        let mut values = Vec::new();
+        value
+            .serialize(Values(&mut values))
+            .map_err(|e| RequestError::invalid(Source::Query, name, e))?;
+        match style {
+            QueryStyle::Comma if !values.is_empty() => {
+                self.query.push((name.to_owned(), values.join(",")));
+            }
+            QueryStyle::Brackets => self
+                .query
+                .extend(values.into_iter().map(|v| (format!("{name}[]"), v))),
+            _ => self
+                .query
+                .extend(values.into_iter().map(|v| (name.to_owned(), v))),
+        }
+        Ok(())
+    }
+
+    /// Set the encoded body and its `Content-Type`.
+    pub fn body(&mut self, content_type: &'static str, bytes: Vec<u8>) {
+        self.body = Some((content_type, bytes));
+    }
+
+    /// Fill the route template, append the query string and assemble the request.
+    pub fn finish(mut self, method: Method, template: &str) -> Result<Request, RequestError> {
+        let mut uri = fill_template(template, &mut self.params)?;
+        if let Some((name, _)) = self.params.first() {
+            return Err(RequestError::UnusedParam { name });
+        }
+        if !self.query.is_empty() {
+            let query = form_urlencoded::Serializer::new(String::new())
+                .extend_pairs(&self.query)
+                .finish();
+            uri.push(if uri.contains('?') { '&' } else { '?' });
+            uri.push_str(&query);
+        }
+        let uri = Uri::try_from(uri).map_err(|e| RequestError::Uri(e.into()))?;
+        if !self.cookies.is_empty() {
+            let cookie = HeaderValue::try_from(self.cookies.join("; "))
+                .map_err(|e| RequestError::invalid(Source::Cookie, "cookie", e))?;
+            self.headers.append(COOKIE, cookie);
+        }
+        let body = match self.body {
+            Some((content_type, bytes)) => {
+                self.headers
+                    .insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+                Body::from(bytes)
+            }
+            None => Body::empty(),
+        };
+        let mut request = Request::new(body);
+        *request.method_mut() = method;
+        *request.uri_mut() = uri;
+        *request.headers_mut() = self.headers;
+        Ok(request)
+    }
+}
+
+/// Join the values of a `#[uri]` or `#[cookie]` collection field with its delimiter.
+pub fn join<I>(values: I, delimiter: &str) -> String
+where
+    I: IntoIterator,
+    I::Item: Display,
+{
+    let mut joined = String::new();
+    for (i, value) in values.into_iter().enumerate() {
+        if i > 0 {
+            joined.push_str(delimiter);
+        }
+        joined.push_str(&value.to_string());
+    }
+    joined
+}
+
+/// Replace every `{name}` or `{*name}` placeholder with its value, removing used params.
+fn fill_template(
+    template: &str,
+    params: &mut Vec<(&'static str, String)>,
+) -> Result<String, RequestError> {
+    let mut uri = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        uri.push_str(&rest[..start]);
+        let placeholder = &rest[start + 1..start + len];
+        let (name, catch_all) = match placeholder.strip_prefix('*') {
+            Some(name) => (name, true),
+            None => (placeholder, false),
+        };
+        let Some(index) = params.iter().position(|(param, _)| *param == name) else {
+            return Err(RequestError::MissingParam {
+                name: name.to_owned(),
+            });
+        };
+        let (_, value) = params.remove(index);
+        encode_path(&mut uri, &value, catch_all);
+        rest = &rest[start + len + 1..];
+    }
+    uri.push_str(rest);
+    Ok(uri)
+}
+
+/// Percent-encode only what cannot appear in a path segment, so plain values stay readable.
+fn encode_path(uri: &mut String, value: &str, catch_all: bool) {
+    for byte in value.bytes() {
+        match byte {
+            b'/' if catch_all => uri.push('/'),
+            b'A'..=b'Z'
+            | b'a'..=b'z'
+            | b'0'..=b'9'
+            | b'-'
+            | b'.'
+            | b'_'
+            | b'~'
+            | b'!'
+            | b'$'
+            | b'&'
+            | b'\''
+            | b'('
+            | b')'
+            | b'*'
+            | b'+'
+            | b','
+            | b';'
+            | b'='
+            | b':'
+            | b'@' => uri.push(byte as char),
+            _ => uri.push_str(&format!("%{byte:02X}")),
+        }
+    }
+}
+
+/// A field could not be written to the request.
+#[derive(Debug)]
+pub enum RequestError {
+    /// A value is not valid in its source, e.g. a header value with a newline.
+    Invalid {
+        source: Source,
+        name: &'static str,
+        error: BoxError,
+    },
+    /// The body failed to encode.
+    Body { source: Source, error: BoxError },
+    /// The route template has a placeholder no `#[uri]` field fills.
+    MissingParam { name: String },
+    /// A `#[uri]` field has no placeholder in the route template.
+    UnusedParam { name: &'static str },
+    /// The filled template is not a valid URI.
+    Uri(BoxError),
+}
+
+impl RequestError {
+    pub fn invalid<E>(source: Source, name: &'static str, error: E) -> Self
+    where
+        E: Into<BoxError>,
+    {
+        Self::Invalid {
+            source,
+            name,
+            error: error.into(),
+        }
+    }
+
+    pub fn body<E>(source: Source, error: E) -> Self
+    where
+        E: Into<BoxError>,
+    {
+        Self::Body {
+            source,
+            error: error.into(),
+        }
+    }
+}
+
+impl Display for RequestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Invalid {
+                source,
+                name,
+                error,
+            } => write!(f, "invalid {source} `{name}`: {error}"),
+            Self::Body { source, error } => write!(f, "failed to encode {source} body: {error}"),
+            Self::MissingParam { name } => write!(f, "no value for path placeholder `{name}`"),
+            Self::UnusedParam { name } => {
+                write!(f, "path param `{name}` has no placeholder in the route")
+            }
+            Self::Uri(error) => write!(f, "invalid request uri: {error}"),
+        }
+    }
+}
+
+impl Error for RequestError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Invalid { error, .. } | Self::Body { error, .. } | Self::Uri(error) => {
+                Some(error.as_ref())
+            }
+            Self::MissingParam { .. } | Self::UnusedParam { .. } => None,
+        }
+    }
+}
+
+/// Serializes a query field into its string values: scalars give one, `None` none and
+/// sequences one per element.
+struct Values<'a>(&'a mut Vec<String>);
+
+type SerError = serde::de::value::Error;
+
+macro_rules! serialize_display {
+    ($($method:ident: $ty:ty)*) => {
+        $(
+            fn $method(self, v: $ty) -> Result<(), SerError> {
+                self.0.push(v.to_string());
+                Ok(())
+            }
+        )*
+    };
+}
+
+fn unsupported(what: &str) -> SerError {
+    ser::Error::custom(format!("{what} cannot be written to a query string"))
+}
+
+impl<'a> ser::Serializer for Values<'a> {
+    type Ok = ();
+    type Error = SerError;
+    type SerializeSeq = Self;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Impossible<(), SerError>;
+    type SerializeTupleVariant = Impossible<(), SerError>;
+    type SerializeMap = Impossible<(), SerError>;
+    type SerializeStruct = Impossible<(), SerError>;
+    type SerializeStructVariant = Impossible<(), SerError>;
+
+    serialize_display! {
+        serialize_bool: bool
+        serialize_i8: i8
+        serialize_i16: i16
+        serialize_i32: i32
+        serialize_i64: i64
+        serialize_i128: i128
+        serialize_u8: u8
+        serialize_u16: u16
+        serialize_u32: u32
+        serialize_u64: u64
+        serialize_u128: u128
+        serialize_f32: f32
+        serialize_f64: f64
+        serialize_char: char
+        serialize_str: &str
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<(), SerError> {
+        Err(unsupported("bytes"))
+    }
+
+    fn serialize_none(self) -> Result<(), SerError> {
+        Ok(())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), SerError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), SerError> {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), SerError> {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<(), SerError> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), SerError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<(), SerError> {
+        Err(unsupported("enum variants with data"))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self, SerError> {
+        Ok(self)
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self, SerError> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, SerError> {
+        Err(unsupported("tuple structs"))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, SerError> {
+        Err(unsupported("enum variants with data"))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, SerError> {
+        Err(unsupported("maps"))
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, SerError> {
+        Err(unsupported("structs"))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, SerError> {
+        Err(unsupported("enum variants with data"))
+    }
+}
+
+impl ser::SerializeSeq for Values<'_> {
+    type Ok = ();
+    type Error = SerError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerError> {
+        value.serialize(Values(self.0))
+    }
+
+    fn end(self) -> Result<(), SerError> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeTuple for Values<'_> {
+    type Ok = ();
+    type Error = SerError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerError> {
+        value.serialize(Values(self.0))
+    }
+
+    fn end(self) -> Result<(), SerError> {
+        Ok(())
+    }
+}
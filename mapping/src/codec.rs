@@ -0,0 +1,112 @@
+//! Pluggable body codecs for `#[msgpack]`, `#[cbor]`, `#[xml]`, `#[protobuf]` and
+//! `#[body(codec = ...)]`.
+//!
+//! The built-in codecs sit behind the `msgpack`, `cbor`, `xml` and `protobuf` features. Any other
+//! serde-based format can be plugged in by implementing [`BodyCodec`] and naming it in
+//! `#[body(codec = path::to::Codec)]`.
+
+use serde::de::{DeserializeOwned, value::MapDeserializer};
+use volo_http::error::BoxError;
+
+use crate::body::BodyKind;
+
+/// A serde-based body format selected by `Content-Type`.
+pub trait BodyCodec {
+    /// Short name used in rejections and `#[mapping(accept = [...])]` messages.
+    const NAME: &'static str;
+    /// Media types (`type/subtype`, lowercase) decoded by this codec; the first one is
+    /// advertised in rejections.
+    const MEDIA_TYPES: &'static [&'static str];
+    /// Structured syntax suffix (`xml` for `application/*+xml`) also decoded by this codec.
+    const SUFFIX: Option<&'static str> = None;
+    const KIND: BodyKind = BodyKind::Codec {
+        name: Self::NAME,
+        media_types: Self::MEDIA_TYPES,
+        suffix: Self::SUFFIX,
+    };
+
+    fn decode<T>(bytes: &[u8]) -> Result<T, BoxError>
+    where
+        T: DeserializeOwned;
+}
+
+/// Decode `T` as if the body were an empty map, so `#[serde(default)]` fields still apply when
+/// the request carries a different body kind or none at all.
+pub fn decode_empty<T>() -> Result<T, BoxError>
+where
+    T: DeserializeOwned,
+{
+    let empty =
+        MapDeserializer::<_, serde::de::value::Error>::new(std::iter::empty::<(&str, &str)>());
+    Ok(T::deserialize(empty)?)
+}
+
+/// MessagePack, `application/msgpack`.
+#[cfg(feature = "msgpack")]
+pub struct MsgPack;
+
+#[cfg(feature = "msgpack")]
+impl BodyCodec for MsgPack {
+    const NAME: &'static str = "msgpack";
+    const MEDIA_TYPES: &'static [&'static str] = &[
+        "application/msgpack",
+        "application/x-msgpack",
+        "application/vnd.msgpack",
+    ];
+
+    fn decode<T>(bytes: &[u8]) -> Result<T, BoxError>
+    where
+        T: DeserializeOwned,
+    {
+        Ok(rmp_serde::from_slice(bytes)?)
+    }
+}
+
+/// CBOR, `application/cbor`.
+#[cfg(feature = "cbor")]
+pub struct Cbor;
+
+#[cfg(feature = "cbor")]
+impl BodyCodec for Cbor {
+    const NAME: &'static str = "cbor";
+    const MEDIA_TYPES: &'static [&'static str] = &["application/cbor"];
+
+    fn decode<T>(bytes: &[u8]) -> Result<T, BoxError>
+    where
+        T: DeserializeOwned,
+    {
+        Ok(ciborium::from_reader(bytes)?)
+    }
+}
+
+/// XML, `application/xml`, `text/xml` or any `+xml` media type.
+///
+/// The root element name is not checked; its children map to the struct fields.
+#[cfg(feature = "xml")]
+pub struct Xml;
+
+#[cfg(feature = "xml")]
+impl BodyCodec for Xml {
+    const NAME: &'static str = "xml";
+    const MEDIA_TYPES: &'static [&'static str] = &["application/xml", "text/xml"];
+    const SUFFIX: Option<&'static str> = Some("xml");
+
+    fn decode<T>(bytes: &[u8]) -> Result<T, BoxError>
+    where
+        T: DeserializeOwned,
+    {
+        Ok(quick_xml::de::from_str(std::str::from_utf8(bytes)?)?)
+    }
+}
+
+/// Decode a whole protobuf body into the `#[protobuf]` field's message type.
+///
+/// Protobuf messages are not serde types, so unlike the other codecs the body maps to a single
+/// field rather than to the generated `XxxMode` struct.
+#[cfg(feature = "protobuf")]
+pub fn decode_protobuf<T>(bytes: &[u8]) -> Result<T, BoxError>
+where
+    T: prost::Message + Default,
+{
+    Ok(T::decode(bytes)?)
+}
@@ -0,0 +1,26 @@
+//! `Cookie` header parsing shared by `#[cookie]` fields and fallback chains.
+//!
+//! Pairs are split on the raw header bytes, so a cookie set by another application on the same
+//! domain that is not valid UTF-8 never affects the cookies a struct actually binds.
+
+use volo_http::http::{HeaderMap, header::COOKIE};
+
+/// Every `name=value` pair of every `Cookie` header, in order.
+///
+/// Names and values are trimmed and surrounding double quotes are removed from values. Pairs
+/// without `=` are skipped.
+pub fn pairs(headers: &HeaderMap) -> impl Iterator<Item = (&[u8], &[u8])> {
+    headers
+        .get_all(COOKIE)
+        .into_iter()
+        .flat_map(|v| v.as_bytes().split(|b| *b == b';'))
+        .filter_map(|pair| {
+            let at = pair.iter().position(|b| *b == b'=')?;
+            let (k, v) = (pair[..at].trim_ascii(), pair[at + 1..].trim_ascii());
+            let v = v
+                .strip_prefix(b"\"")
+                .and_then(|v| v.strip_suffix(b"\""))
+                .unwrap_or(v);
+            Some((k, v))
+        })
+}
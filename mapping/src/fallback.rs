@@ -0,0 +1,147 @@
+//! Support for `#[mapping(from(...))]` fallback chains.
+//!
+//! A chain field is read from each listed source in order; the first source that carries the
+//! value wins. With `conflict = "reject"` every source is read and two sources carrying different
+//! raw values reject the request with [`MappingRejection::Conflict`]. Declaring the field as
+//! [`Sourced<T>`] records which source matched.
+
+use std::{borrow::Cow, ops::Deref};
+
+use volo_http::{context::ServerContext, http::request::Parts};
+
+use crate::{
+    cookie,
+    rejection::{MappingRejection, Source},
+};
+
+/// A chain field's value together with the source it was read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sourced<T> {
+    pub value: T,
+    pub source: Source,
+    /// The wire name the value was found under.
+    pub name: &'static str,
+}
+
+impl<T> Sourced<T> {
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T> Deref for Sourced<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+/// Raw value of `name` in a header, path, query or cookie `source`, `None` when absent.
+///
+/// Values that are not valid UTF-8 reject the request unless `lenient`, in which case they count
+/// as absent. Other sources are never consulted by fallback chains and are always absent.
+pub fn lookup<'a>(
+    cx: &'a ServerContext,
+    parts: &'a Parts,
+    source: Source,
+    name: &'static str,
+    field: &'static str,
+    lenient: bool,
+) -> Result<Option<Cow<'a, str>>, MappingRejection> {
+    match source {
+        Source::Header => match parts.headers.get(name).map(|v| v.to_str()) {
+            Some(Ok(v)) => Ok(Some(Cow::Borrowed(v))),
+            Some(Err(_)) if lenient => Ok(None),
+            Some(Err(e)) => Err(MappingRejection::invalid(source, name, field, e)),
+            None => Ok(None),
+        },
+        Source::Uri => Ok(cx
+            .params()
+            .iter()
+            .find(|(k, _)| k.as_str() == name)
+            .map(|(_, v)| Cow::Borrowed(v.as_str()))),
+        Source::Query => Ok(parts.uri.query().and_then(|query| {
+            form_urlencoded::parse(query.as_bytes())
+                .find(|(k, _)| k == name)
+                .map(|(_, v)| v)
+        })),
+        Source::Cookie => {
+            for (k, v) in cookie::pairs(&parts.headers) {
+                if k != name.as_bytes() {
+                    continue;
+                }
+                return match std::str::from_utf8(v) {
+                    Ok(v) => Ok(Some(Cow::Borrowed(v))),
+                    Err(_) if lenient => Ok(None),
+                    Err(e) => Err(MappingRejection::invalid_value(
+                        source,
+                        name,
+                        field,
+                        &String::from_utf8_lossy(v),
+                        e,
+                    )),
+                };
+            }
+            Ok(None)
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Every raw value of `name` in `source`, for collection fields; empty when absent.
+///
+/// Repeated query keys, headers and cookies all contribute, as they do for `#[query]`,
+/// `#[header]` and `#[cookie]` collection fields. Invalid values are handled as in [`lookup`].
+pub fn lookup_all<'a>(
+    cx: &'a ServerContext,
+    parts: &'a Parts,
+    source: Source,
+    name: &'static str,
+    field: &'static str,
+    lenient: bool,
+) -> Result<Vec<Cow<'a, str>>, MappingRejection> {
+    let mut values = Vec::new();
+    match source {
+        Source::Header => {
+            for v in parts.headers.get_all(name) {
+                match v.to_str() {
+                    Ok(v) => values.push(Cow::Borrowed(v)),
+                    Err(_) if lenient => {}
+                    Err(e) => return Err(MappingRejection::invalid(source, name, field, e)),
+                }
+            }
+        }
+        Source::Query => {
+            if let Some(query) = parts.uri.query() {
+                values.extend(
+                    form_urlencoded::parse(query.as_bytes())
+                        .filter(|(k, _)| k == name)
+                        .map(|(_, v)| v),
+                );
+            }
+        }
+        Source::Cookie => {
+            for (k, v) in cookie::pairs(&parts.headers) {
+                if k != name.as_bytes() {
+                    continue;
+                }
+                match std::str::from_utf8(v) {
+                    Ok(v) => values.push(Cow::Borrowed(v)),
+                    Err(_) if lenient => {}
+                    Err(e) => {
+                        return Err(MappingRejection::invalid_value(
+                            source,
+                            name,
+                            field,
+                            &String::from_utf8_lossy(v),
+                            e,
+                        ));
+                    }
+                }
+            }
+        }
+        _ => values.extend(lookup(cx, parts, source, name, field, lenient)?),
+    }
+    Ok(values)
+}
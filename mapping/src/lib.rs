@@ -1,16 +1,22 @@
-use proc_macro::TokenStream;
-use syn::{DeriveInput, parse_macro_input};
-mod deserialize;
-mod field_attr;
-mod helper;
-mod mapping;
-
-#[proc_macro_derive(Mapping, attributes(ext, json, form, uri, header, query, serde))]
-pub fn param_bind_derive(input: TokenStream) -> TokenStream {
-    let mut input = parse_macro_input!(input as DeriveInput);
-    mapping::expand_params_mapping(&mut input)
-        .unwrap_or_else(syn::Error::into_compile_error)
-        .into()
-}
+pub use mapping_derive::{IntoResponseMapping, Mapping};
 
+pub mod body;
+pub mod client;
+pub mod codec;
+pub mod cookie;
+pub mod fallback;
+pub mod multipart;
+pub mod openapi;
+pub mod parts;
+pub mod query;
+pub mod rejection;
+pub mod response;
+pub mod validate;
 
+pub use body::BodyKind;
+pub use client::IntoRequest;
+pub use fallback::Sourced;
+pub use multipart::UploadFile;
+pub use openapi::{ApiMethod, ApiRouter, OpenApi, OpenApiSchema};
+pub use parts::FromParts;
+pub use rejection::{MappingRejection, Source, ValidationError};
@@ -0,0 +1,170 @@
+//! Support types for `#[multipart]` fields.
+
+use bytes::Bytes;
+use http_body_util::BodyExt;
+use serde::de::DeserializeOwned;
+use volo_http::{
+    body::Body,
+    http::header::{CONTENT_TYPE, HeaderMap},
+};
+
+use crate::rejection::{MappingRejection, Source};
+
+/// Size limits applied while streaming a `multipart/form-data` body.
+///
+/// Defaults to 2 MiB per part and 16 MiB for the whole body. A part or body over its limit is
+/// rejected with `413 Payload Too Large`.
+#[derive(Debug, Clone)]
+pub struct MultipartLimits {
+    part: u64,
+    total: u64,
+    fields: Vec<(&'static str, u64)>,
+}
+
+impl MultipartLimits {
+    pub const DEFAULT_PART_LIMIT: u64 = 2 * 1024 * 1024;
+    pub const DEFAULT_TOTAL_LIMIT: u64 = 16 * 1024 * 1024;
+
+    pub fn new(part: u64, total: u64) -> Self {
+        Self {
+            part,
+            total,
+            fields: Vec::new(),
+        }
+    }
+
+    /// Override the per-part limit for the part called `name`.
+    pub fn field(mut self, name: &'static str, limit: u64) -> Self {
+        self.fields.push((name, limit));
+        self
+    }
+
+    fn constraints(&self) -> multer::Constraints {
+        let size_limit = self.fields.iter().fold(
+            multer::SizeLimit::new()
+                .whole_stream(self.total)
+                .per_field(self.part),
+            |size_limit, (name, limit)| size_limit.for_field(*name, *limit),
+        );
+        multer::Constraints::new().size_limit(size_limit)
+    }
+}
+
+impl Default for MultipartLimits {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_PART_LIMIT, Self::DEFAULT_TOTAL_LIMIT)
+    }
+}
+
+/// A file part of a `multipart/form-data` body.
+#[derive(Debug, Clone)]
+pub struct UploadFile {
+    file_name: Option<String>,
+    content_type: Option<mime::Mime>,
+    bytes: Bytes,
+}
+
+impl UploadFile {
+    pub fn file_name(&self) -> Option<&str> {
+        self.file_name.as_deref()
+    }
+
+    pub fn content_type(&self) -> Option<&mime::Mime> {
+        self.content_type.as_ref()
+    }
+
+    pub fn bytes(&self) -> &Bytes {
+        &self.bytes
+    }
+
+    pub fn into_bytes(self) -> Bytes {
+        self.bytes
+    }
+}
+
+/// Streaming reader over the parts of a `multipart/form-data` body.
+pub struct MultipartReader {
+    inner: multer::Multipart<'static>,
+}
+
+impl MultipartReader {
+    pub fn new(
+        headers: &HeaderMap,
+        body: Body,
+        limits: &MultipartLimits,
+    ) -> Result<Self, MappingRejection> {
+        let content_type = headers
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .ok_or(multer::Error::NoMultipart)
+            .map_err(multipart_rejection)?;
+        let boundary = multer::parse_boundary(content_type).map_err(multipart_rejection)?;
+        Ok(Self {
+            inner: multer::Multipart::with_constraints(
+                body.into_data_stream(),
+                boundary,
+                limits.constraints(),
+            ),
+        })
+    }
+
+    pub async fn next_part(&mut self) -> Result<Option<Part>, MappingRejection> {
+        let field = self.inner.next_field().await.map_err(multipart_rejection)?;
+        Ok(field.map(|inner| Part { inner }))
+    }
+}
+
+/// A single part yielded by [`MultipartReader`].
+pub struct Part {
+    inner: multer::Field<'static>,
+}
+
+impl Part {
+    pub fn name(&self) -> Option<&str> {
+        self.inner.name()
+    }
+
+    pub async fn text(self) -> Result<String, MappingRejection> {
+        self.inner.text().await.map_err(multipart_rejection)
+    }
+
+    pub async fn file(self) -> Result<UploadFile, MappingRejection> {
+        let file_name = self.inner.file_name().map(str::to_owned);
+        let content_type = self.inner.content_type().cloned();
+        let bytes = self.inner.bytes().await.map_err(multipart_rejection)?;
+        Ok(UploadFile {
+            file_name,
+            content_type,
+            bytes,
+        })
+    }
+}
+
+/// Decode the collected text parts into the generated `MultipartMode` struct.
+///
+/// Text parts go through the same serde path as urlencoded forms, so `#[serde(...)]`
+/// attributes and non-string field types behave as they do for `#[form]`.
+pub fn decode_text<T>(pairs: &[(String, String)]) -> Result<T, MappingRejection>
+where
+    T: DeserializeOwned,
+{
+    let encoded = serde_urlencoded::to_string(pairs)
+        .map_err(|e| MappingRejection::decode(Source::Multipart, e))?;
+    serde_urlencoded::from_str(&encoded).map_err(|e| MappingRejection::decode(Source::Multipart, e))
+}
+
+fn multipart_rejection(e: multer::Error) -> MappingRejection {
+    match e {
+        multer::Error::FieldSizeExceeded { limit, field_name } => MappingRejection::TooLarge {
+            source: Source::Multipart,
+            name: field_name,
+            limit,
+        },
+        multer::Error::StreamSizeExceeded { limit } => MappingRejection::TooLarge {
+            source: Source::Multipart,
+            name: None,
+            limit,
+        },
+        e => MappingRejection::decode(Source::Multipart, e),
+    }
+}
@@ -0,0 +1,408 @@
+//! OpenAPI 3.1 descriptions of `#[derive(Mapping)]` structs.
+//!
+//! The derive implements [`OpenApiSchema`] from what it already knows about each field: its
+//! source, wire name, whether it is optional or repeated and its type. `#[validate]` rules become
+//! schema constraints and doc comments become descriptions. [`ApiRouter`] registers each route
+//! together with its operation, so the served [`OpenApi`] document always matches the router.
+
+use std::{
+    collections::{BTreeMap, btree_map::Entry},
+    fmt::Display,
+};
+
+use serde::{Serialize, Serializer, ser::SerializeMap};
+use volo_http::{
+    http::Method,
+    server::{
+        Router,
+        extract::Json,
+        route::{MethodRouter, get},
+    },
+};
+
+/// Describe the request a `#[derive(Mapping)]` type extracts.
+pub trait OpenApiSchema {
+    /// Header, path, query and cookie parameters.
+    fn openapi_parameters() -> Vec<Parameter>;
+
+    /// The request body for each accepted media type, `None` when no body is read.
+    fn openapi_request_body() -> Option<RequestBody>;
+
+    /// The doc comment of the type.
+    fn openapi_description() -> Option<&'static str> {
+        None
+    }
+}
+
+/// A JSON Schema, limited to what field types and `#[validate]` rules can express.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Schema {
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub ty: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub items: Option<Box<Schema>>,
+    #[serde(skip_serializing_if = "Vec::is_empty", serialize_with = "ordered_map")]
+    pub properties: Vec<(String, Schema)>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub required: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub additional_properties: Option<Box<Schema>>,
+    #[serde(rename = "enum", skip_serializing_if = "Vec::is_empty")]
+    pub enum_values: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub minimum: Option<Number>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub maximum: Option<Number>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_length: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_length: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_items: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_items: Option<usize>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub unique_items: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+}
+
+impl Schema {
+    /// A schema of the given `type` and optional `format`.
+    pub fn typed(ty: &'static str, format: Option<&'static str>) -> Self {
+        Self {
+            ty: Some(ty),
+            format,
+            ..Self::default()
+        }
+    }
+
+    pub fn array(items: Schema) -> Self {
+        Self {
+            ty: Some("array"),
+            items: Some(Box::new(items)),
+            ..Self::default()
+        }
+    }
+
+    /// An array whose values are distinct, such as a `HashSet<T>`.
+    pub fn set(items: Schema) -> Self {
+        Self {
+            unique_items: true,
+            ..Self::array(items)
+        }
+    }
+
+    /// An object with arbitrary keys, such as a `HashMap<String, V>`.
+    pub fn map(values: Schema) -> Self {
+        Self {
+            ty: Some("object"),
+            additional_properties: Some(Box::new(values)),
+            ..Self::default()
+        }
+    }
+
+    /// A string limited to `values`, used for enum tags.
+    pub fn one_of(values: &[&str]) -> Self {
+        Self {
+            enum_values: values.iter().map(|v| v.to_string()).collect(),
+            ..Self::typed("string", None)
+        }
+    }
+
+    /// Add a property, marking it required when `required`.
+    pub fn property(mut self, name: &str, schema: Schema, required: bool) -> Self {
+        self.ty = Some("object");
+        if required {
+            self.required.push(name.to_owned());
+        }
+        self.properties.push((name.to_owned(), schema));
+        self
+    }
+
+    pub fn description(mut self, description: Option<&str>) -> Self {
+        self.description = description.map(str::to_owned);
+        self
+    }
+
+    /// `#[validate(range(..))]`; arrays constrain their items.
+    pub fn range(mut self, minimum: Option<Number>, maximum: Option<Number>) -> Self {
+        match self.items.take() {
+            Some(items) => self.items = Some(Box::new(items.range(minimum, maximum))),
+            None => {
+                self.minimum = minimum;
+                self.maximum = maximum;
+            }
+        }
+        self
+    }
+
+    /// `#[validate(length(..))]`; counts items for arrays and characters otherwise.
+    pub fn length(mut self, min: Option<usize>, max: Option<usize>) -> Self {
+        if self.ty == Some("array") {
+            self.min_items = min;
+            self.max_items = max;
+        } else {
+            self.min_length = min;
+            self.max_length = max;
+        }
+        self
+    }
+
+    /// `#[validate(regex = "..")]`; arrays constrain their items.
+    pub fn pattern(mut self, pattern: &str) -> Self {
+        match self.items.take() {
+            Some(items) => self.items = Some(Box::new(items.pattern(pattern))),
+            None => self.pattern = Some(pattern.to_owned()),
+        }
+        self
+    }
+
+    /// `#[validate(email)]`; arrays constrain their items.
+    pub fn email(mut self) -> Self {
+        match self.items.take() {
+            Some(items) => self.items = Some(Box::new(items.email())),
+            None => self.format = Some("email"),
+        }
+        self
+    }
+}
+
+/// A numeric bound, kept integral when the bound is.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Number {
+    Int(i64),
+    Float(f64),
+}
+
+impl Number {
+    /// Read a `#[validate(range(..))]` bound through its `Display` output; `None` when the bound
+    /// is not a number.
+    pub fn parse<T: Display + ?Sized>(value: &T) -> Option<Self> {
+        let value = value.to_string();
+        value
+            .parse()
+            .map(Self::Int)
+            .ok()
+            .or_else(|| value.parse().ok().map(Self::Float))
+    }
+}
+
+/// Where a parameter is read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ParameterIn {
+    Header,
+    Path,
+    Query,
+    Cookie,
+}
+
+/// A header, path, query or cookie parameter.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Parameter {
+    pub name: String,
+    #[serde(rename = "in")]
+    pub location: ParameterIn,
+    pub required: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub explode: Option<bool>,
+    pub schema: Schema,
+}
+
+/// The body schema for each accepted media type.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RequestBody {
+    pub required: bool,
+    #[serde(serialize_with = "ordered_map")]
+    pub content: Vec<(&'static str, MediaType)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MediaType {
+    pub schema: Schema,
+}
+
+/// One method on one path.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Operation {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<&'static str>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub parameters: Vec<Parameter>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_body: Option<RequestBody>,
+    pub responses: BTreeMap<&'static str, Response>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Response {
+    pub description: &'static str,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Info {
+    pub title: String,
+    pub version: String,
+}
+
+/// An OpenAPI 3.1 document built from the extractor types of each route.
+///
+/// [`ApiRouter`] fills it in while building the router; [`OpenApi::operation`] is available for
+/// routes registered elsewhere.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OpenApi {
+    openapi: &'static str,
+    pub info: Info,
+    pub paths: BTreeMap<String, BTreeMap<String, Operation>>,
+}
+
+impl OpenApi {
+    pub fn new(title: impl Into<String>, version: impl Into<String>) -> Self {
+        Self {
+            openapi: "3.1.0",
+            info: Info {
+                title: title.into(),
+                version: version.into(),
+            },
+            paths: BTreeMap::new(),
+        }
+    }
+
+    /// Describe `method` on the route `path`, whose handler extracts `T`.
+    ///
+    /// `path` uses the `Router` syntax; catch-all `{*rest}` placeholders are written as `{rest}`.
+    pub fn operation<T: OpenApiSchema>(mut self, method: Method, path: &str) -> Self {
+        let operation = Operation {
+            description: T::openapi_description(),
+            parameters: T::openapi_parameters(),
+            request_body: T::openapi_request_body(),
+            responses: BTreeMap::from([("200", Response { description: "OK" })]),
+        };
+        self.paths
+            .entry(path.replace("{*", "{"))
+            .or_default()
+            .insert(method.as_str().to_ascii_lowercase(), operation);
+        self
+    }
+
+    /// A router serving the document as JSON on `GET path`, to merge into the service router.
+    pub fn router(self, path: &str) -> Router {
+        Router::new().route(path, get(move || async move { Json(self) }))
+    }
+}
+
+/// The methods `Router` can route, as taken by [`ApiRouter::route`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ApiMethod {
+    Options,
+    Get,
+    Post,
+    Put,
+    Delete,
+    Head,
+    Trace,
+    Connect,
+    Patch,
+}
+
+impl From<ApiMethod> for Method {
+    fn from(method: ApiMethod) -> Self {
+        match method {
+            ApiMethod::Options => Method::OPTIONS,
+            ApiMethod::Get => Method::GET,
+            ApiMethod::Post => Method::POST,
+            ApiMethod::Put => Method::PUT,
+            ApiMethod::Delete => Method::DELETE,
+            ApiMethod::Head => Method::HEAD,
+            ApiMethod::Trace => Method::TRACE,
+            ApiMethod::Connect => Method::CONNECT,
+            ApiMethod::Patch => Method::PATCH,
+        }
+    }
+}
+
+/// A `Router` builder that documents every route it registers.
+///
+/// Each route names its path and method once, for both the router and the document, e.g.
+/// `.route::<CreateArticle>("/users/{uid}/articles", ApiMethod::Post, any(create))`.
+pub struct ApiRouter {
+    routes: BTreeMap<String, MethodRouter>,
+    doc: OpenApi,
+}
+
+impl ApiRouter {
+    pub fn new(doc: OpenApi) -> Self {
+        Self {
+            routes: BTreeMap::new(),
+            doc,
+        }
+    }
+
+    /// Route `method` requests on `path` to `handler` and describe them with `T`.
+    ///
+    /// `handler` is usually `any(handler_fn)`; only `method` is routed to it, other methods of the
+    /// same path can be added by further calls.
+    pub fn route<T: OpenApiSchema>(
+        mut self,
+        path: &str,
+        method: ApiMethod,
+        handler: MethodRouter,
+    ) -> Self {
+        let route = match self.routes.entry(path.to_owned()) {
+            Entry::Occupied(entry) => entry.remove(),
+            Entry::Vacant(_) => MethodRouter::default(),
+        };
+        let route = match method {
+            ApiMethod::Options => route.options_service(handler),
+            ApiMethod::Get => route.get_service(handler),
+            ApiMethod::Post => route.post_service(handler),
+            ApiMethod::Put => route.put_service(handler),
+            ApiMethod::Delete => route.delete_service(handler),
+            ApiMethod::Head => route.head_service(handler),
+            ApiMethod::Trace => route.trace_service(handler),
+            ApiMethod::Connect => route.connect_service(handler),
+            ApiMethod::Patch => route.patch_service(handler),
+        };
+        self.routes.insert(path.to_owned(), route);
+        self.doc = self.doc.operation::<T>(method.into(), path);
+        self
+    }
+
+    /// The document built so far.
+    pub fn doc(&self) -> &OpenApi {
+        &self.doc
+    }
+
+    /// The registered routes plus the document served as JSON on `GET doc_path`.
+    pub fn into_router(self, doc_path: &str) -> Router {
+        self.routes
+            .into_iter()
+            .fold(Router::new(), |router, (path, route)| {
+                router.route(path, route)
+            })
+            .merge(self.doc.router(doc_path))
+    }
+}
+
+fn ordered_map<S, K, V>(entries: &[(K, V)], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    K: Serialize,
+    V: Serialize,
+{
+    let mut map = serializer.serialize_map(Some(entries.len()))?;
+    for (key, value) in entries {
+        map.serialize_entry(key, value)?;
+    }
+    map.end()
+}
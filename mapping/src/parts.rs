@@ -0,0 +1,23 @@
+//! Body-less extraction, used to flatten one `#[derive(Mapping)]` struct into another.
+
+use std::future::Future;
+
+use volo_http::{context::ServerContext, http::request::Parts};
+
+use crate::rejection::MappingRejection;
+
+/// Extract `Self` from the request head alone.
+///
+/// `#[derive(Mapping)]` implements this for every struct that binds no body fields. A parent
+/// struct pulls such a type in with `#[mapping(flatten)]`, so shared header, query, uri, cookie
+/// and ext bindings can be reused while the parent stays the only reader of the body.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` cannot be flattened into a `Mapping` struct",
+    note = "flattened types must `#[derive(Mapping)]` and bind no body fields"
+)]
+pub trait FromParts: Sized {
+    fn from_parts(
+        cx: &mut ServerContext,
+        parts: &Parts,
+    ) -> impl Future<Output = Result<Self, MappingRejection>> + Send;
+}
@@ -0,0 +1,336 @@
+//! Query string decoding for `#[query]` fields.
+//!
+//! Unlike plain `serde_urlencoded`, repeated keys are grouped so `Vec<T>` fields can bind
+//! `?tag=a&tag=b`. `#[query(style = "comma")]` and `#[query(style = "brackets")]` additionally
+//! accept `?tag=a,b` and `?tag[]=a&tag[]=b`. Scalar fields still expect a single value.
+
+use std::collections::HashMap;
+
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, Error as _, IntoDeserializer, MapAccess, Visitor,
+    value::{Error, SeqDeserializer},
+};
+
+use crate::rejection::{MappingRejection, Source};
+
+/// How a `Vec<T>` query field spells its values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryStyle {
+    /// `?tag=a&tag=b`, the default.
+    Repeat,
+    /// `?tag=a,b`; repeated keys are accepted too.
+    Comma,
+    /// `?tag[]=a&tag[]=b`; the bare key is accepted too.
+    Brackets,
+}
+
+/// Decode the query string into the generated `QueryMode` struct.
+///
+/// `styles` lists the fields, by their query name, that use a style other than
+/// [`QueryStyle::Repeat`].
+pub fn decode<T>(query: &str, styles: &[(&str, QueryStyle)]) -> Result<T, MappingRejection>
+where
+    T: DeserializeOwned,
+{
+    let style = |key: &str| {
+        styles
+            .iter()
+            .find(|(name, _)| *name == key)
+            .map_or(QueryStyle::Repeat, |(_, style)| *style)
+    };
+    let mut index: HashMap<String, usize> = HashMap::new();
+    let mut entries: Vec<(String, Vec<String>)> = Vec::new();
+    for (key, value) in form_urlencoded::parse(query.as_bytes()) {
+        let key = match key.strip_suffix("[]") {
+            Some(name) if style(name) == QueryStyle::Brackets => name.to_owned(),
+            _ => key.into_owned(),
+        };
+        let values = match index.get(&key) {
+            Some(&i) => &mut entries[i].1,
+            None => {
+                index.insert(key.clone(), entries.len());
+                entries.push((key.clone(), Vec::new()));
+                &mut entries.last_mut().unwrap().1
+            }
+        };
+        if style(&key) == QueryStyle::Comma {
+            values.extend(value.split(',').map(str::to_owned));
+        } else {
+            values.push(value.into_owned());
+        }
+    }
+    T::deserialize(QueryDeserializer {
+        entries: entries.into_iter(),
+        value: None,
+    })
+    .map_err(|e| MappingRejection::decode(Source::Query, e))
+}
+
+/// The whole query string as a map of key to every value given for it.
+struct QueryDeserializer {
+    entries: std::vec::IntoIter<(String, Vec<String>)>,
+    value: Option<Vec<String>>,
+}
+
+impl<'de> de::Deserializer<'de> for QueryDeserializer {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_map(self)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        option unit unit_struct newtype_struct seq tuple tuple_struct map struct enum identifier
+        ignored_any
+    }
+}
+
+impl<'de> MapAccess<'de> for QueryDeserializer {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        let Some((key, values)) = self.entries.next() else {
+            return Ok(None);
+        };
+        self.value = Some(values);
+        seed.deserialize(key.into_deserializer()).map(Some)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let values = self
+            .value
+            .take()
+            .ok_or_else(|| Error::custom("value requested before key"))?;
+        seed.deserialize(Values(values))
+    }
+}
+
+/// Every value of one key; sequences take all of them, anything else exactly one.
+struct Values(Vec<String>);
+
+impl Values {
+    fn single(mut self) -> Result<Part, Error> {
+        match self.0.len() {
+            1 => Ok(Part(self.0.pop().unwrap())),
+            n => Err(Error::custom(format!("expected a single value, found {n}"))),
+        }
+    }
+}
+
+macro_rules! forward_to_single {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value, Error>
+            where
+                V: Visitor<'de>,
+            {
+                self.single()?.$method(visitor)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for Values {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        if self.0.len() == 1 {
+            self.single()?.deserialize_any(visitor)
+        } else {
+            self.deserialize_seq(visitor)
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_seq(SeqDeserializer::new(self.0.into_iter().map(Part)))
+    }
+
+    fn deserialize_tuple<V>(self, _len: usize, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_unit_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.single()?.deserialize_unit_struct(name, visitor)
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.single()?.deserialize_struct(name, fields, visitor)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.single()?.deserialize_enum(name, variants, visitor)
+    }
+
+    forward_to_single! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_i128 deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64
+        deserialize_u128 deserialize_f32 deserialize_f64 deserialize_char deserialize_str
+        deserialize_string deserialize_bytes deserialize_byte_buf deserialize_unit
+        deserialize_map deserialize_identifier
+    }
+
+    // Keys no field binds are skipped however often they repeat
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+}
+
+/// One query value, parsed on demand into the requested primitive.
+struct Part(String);
+
+impl<'de> IntoDeserializer<'de, Error> for Part {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+macro_rules! forward_parsed {
+    ($($ty:ident => $method:ident $visit:ident)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value, Error>
+            where
+                V: Visitor<'de>,
+            {
+                match self.0.parse::<$ty>() {
+                    Ok(v) => visitor.$visit(v),
+                    Err(e) => Err(Error::custom(e)),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for Part {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_string(self.0)
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_enum(self.0.into_deserializer())
+    }
+
+    forward_parsed! {
+        bool => deserialize_bool visit_bool
+        i8 => deserialize_i8 visit_i8
+        i16 => deserialize_i16 visit_i16
+        i32 => deserialize_i32 visit_i32
+        i64 => deserialize_i64 visit_i64
+        i128 => deserialize_i128 visit_i128
+        u8 => deserialize_u8 visit_u8
+        u16 => deserialize_u16 visit_u16
+        u32 => deserialize_u32 visit_u32
+        u64 => deserialize_u64 visit_u64
+        u128 => deserialize_u128 visit_u128
+        f32 => deserialize_f32 visit_f32
+        f64 => deserialize_f64 visit_f64
+    }
+
+    serde::forward_to_deserialize_any! {
+        char str string bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
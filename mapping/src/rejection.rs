@@ -0,0 +1,493 @@
+//! Rejections returned by extractors generated with `#[derive(Mapping)]`.
+
+use std::{error::Error, fmt};
+
+use serde::Serialize;
+use volo_http::{
+    error::{BoxError, server::ExtractBodyError},
+    http::StatusCode,
+    response::Response,
+    server::{IntoResponse, extract::Json},
+};
+
+use crate::body::BodyKind;
+
+/// The part of the request a field is read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Source {
+    Header,
+    Uri,
+    Query,
+    Json,
+    Form,
+    Cookie,
+    Multipart,
+    Msgpack,
+    Cbor,
+    Xml,
+    Protobuf,
+    Body,
+    Ext,
+}
+
+impl Source {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Header => "header",
+            Self::Uri => "uri",
+            Self::Query => "query",
+            Self::Json => "json",
+            Self::Form => "form",
+            Self::Cookie => "cookie",
+            Self::Multipart => "multipart",
+            Self::Msgpack => "msgpack",
+            Self::Cbor => "cbor",
+            Self::Xml => "xml",
+            Self::Protobuf => "protobuf",
+            Self::Body => "body",
+            Self::Ext => "ext",
+        }
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Rejection of a `#[derive(Mapping)]` extractor.
+///
+/// Every variant answers with a JSON body such as
+/// `{"code":"invalid_field","source":"header","name":"x-token","field":"token","message":"..."}`.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum MappingRejection {
+    /// The request body could not be collected.
+    Body(ExtractBodyError),
+    /// A value was present but could not be parsed into its field.
+    ///
+    /// `name` is the wire name (header name, path placeholder, query key, ...) and `field` the
+    /// Rust field name. Both are `None` when a whole body or query string failed to decode and
+    /// the error cannot be pinned to a single field. `value` holds the offending raw value when
+    /// it is known.
+    Invalid {
+        source: Source,
+        name: Option<&'static str>,
+        field: Option<&'static str>,
+        value: Option<String>,
+        error: BoxError,
+    },
+    /// A required field was absent from its source.
+    Missing {
+        source: Source,
+        name: &'static str,
+        field: &'static str,
+    },
+    /// A part or the whole body exceeded its size limit; `name` is the part name when known.
+    TooLarge {
+        source: Source,
+        name: Option<String>,
+        limit: u64,
+    },
+    /// The body's `Content-Type` is absent, unknown or not among the `accept`ed kinds.
+    UnsupportedMediaType {
+        content_type: Option<String>,
+        accept: &'static [BodyKind],
+    },
+    /// The body's `Content-Type` names a `charset` other than UTF-8, the only one decoded.
+    UnsupportedCharset {
+        content_type: String,
+        charset: String,
+    },
+    /// Every value was bound but some failed their `#[validate(...)]` checks.
+    Validation(Vec<ValidationError>),
+    /// Two sources of a `#[mapping(from(...), conflict = "reject")]` field carried different
+    /// values; `first` and `second` are the source and wire name of each.
+    Conflict {
+        field: &'static str,
+        first: (Source, &'static str),
+        second: (Source, &'static str),
+    },
+}
+
+/// A single failed `#[validate(...)]` check.
+#[derive(Debug, Clone)]
+pub struct ValidationError {
+    pub source: Source,
+    pub name: &'static str,
+    pub field: &'static str,
+    pub message: String,
+}
+
+impl ValidationError {
+    pub fn new(source: Source, name: &'static str, field: &'static str, message: String) -> Self {
+        Self {
+            source,
+            name,
+            field,
+            message,
+        }
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} `{}` {}", self.source, self.name, self.message)
+    }
+}
+
+impl MappingRejection {
+    /// A single field failed to parse.
+    pub fn invalid<E>(source: Source, name: &'static str, field: &'static str, error: E) -> Self
+    where
+        E: Into<BoxError>,
+    {
+        Self::Invalid {
+            source,
+            name: Some(name),
+            field: Some(field),
+            value: None,
+            error: error.into(),
+        }
+    }
+
+    /// A single field failed to parse from the raw `value`.
+    pub fn invalid_value<E>(
+        source: Source,
+        name: &'static str,
+        field: &'static str,
+        value: &str,
+        error: E,
+    ) -> Self
+    where
+        E: Into<BoxError>,
+    {
+        Self::Invalid {
+            source,
+            name: Some(name),
+            field: Some(field),
+            value: Some(value.to_owned()),
+            error: error.into(),
+        }
+    }
+
+    /// A required field was absent.
+    pub fn missing(source: Source, name: &'static str, field: &'static str) -> Self {
+        Self::Missing {
+            source,
+            name,
+            field,
+        }
+    }
+
+    /// A whole source (body or query string) failed to decode.
+    pub fn decode<E>(source: Source, error: E) -> Self
+    where
+        E: Into<BoxError>,
+    {
+        Self::Invalid {
+            source,
+            name: None,
+            field: None,
+            value: None,
+            error: error.into(),
+        }
+    }
+
+    /// An enum's tag was absent or named no variant.
+    pub fn unknown_tag(
+        source: Source,
+        name: &'static str,
+        value: Option<String>,
+        expected: &'static [&'static str],
+    ) -> Self {
+        Self::Invalid {
+            source,
+            name: Some(name),
+            field: None,
+            value,
+            error: format!("expected one of: {}", expected.join(", ")).into(),
+        }
+    }
+
+    /// A fallback chain field was found with different values in two sources.
+    pub fn conflict(
+        field: &'static str,
+        first: (Source, &'static str),
+        second: (Source, &'static str),
+    ) -> Self {
+        Self::Conflict {
+            field,
+            first,
+            second,
+        }
+    }
+
+    /// The body's `Content-Type` cannot be decoded by this extractor.
+    pub fn unsupported_media_type(
+        content_type: Option<String>,
+        accept: &'static [BodyKind],
+    ) -> Self {
+        Self::UnsupportedMediaType {
+            content_type,
+            accept,
+        }
+    }
+
+    /// The body's `Content-Type` names a `charset` other than UTF-8.
+    pub fn unsupported_charset(content_type: String, charset: String) -> Self {
+        Self::UnsupportedCharset {
+            content_type,
+            charset,
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::Body(ExtractBodyError::Generic(e)) => e.to_status_code(),
+            Self::Body(ExtractBodyError::String(_)) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            // A missing extension is a server-side wiring problem, not a client error
+            Self::Missing {
+                source: Source::Ext,
+                ..
+            } => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::TooLarge { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::UnsupportedMediaType { .. } | Self::UnsupportedCharset { .. } => {
+                StatusCode::UNSUPPORTED_MEDIA_TYPE
+            }
+            Self::Validation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::Body(_) | Self::Invalid { .. } | Self::Missing { .. } | Self::Conflict { .. } => {
+                StatusCode::BAD_REQUEST
+            }
+        }
+    }
+
+    fn code(&self) -> &'static str {
+        match self {
+            Self::Body(_) => "invalid_body",
+            Self::Invalid { .. } => "invalid_field",
+            Self::Missing {
+                source: Source::Ext,
+                ..
+            } => "missing_extension",
+            Self::Missing { .. } => "missing_field",
+            Self::TooLarge { .. } => "payload_too_large",
+            Self::UnsupportedMediaType { .. } => "unsupported_media_type",
+            Self::UnsupportedCharset { .. } => "unsupported_charset",
+            Self::Validation(_) => "validation_failed",
+            Self::Conflict { .. } => "conflicting_field",
+        }
+    }
+}
+
+impl fmt::Display for MappingRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Body(e) => write!(f, "{e}"),
+            Self::Invalid {
+                source,
+                name: Some(name),
+                value: Some(value),
+                error,
+                ..
+            } => write!(f, "invalid {source} `{name}` value `{value}`: {error}"),
+            Self::Invalid {
+                source,
+                name: Some(name),
+                error,
+                ..
+            } => write!(f, "invalid {source} `{name}`: {error}"),
+            Self::Invalid { source, error, .. } => write!(f, "invalid {source}: {error}"),
+            Self::Missing {
+                source: Source::Ext,
+                name,
+                ..
+            } => write!(f, "missing extension `{name}`"),
+            Self::Missing { source, name, .. } => write!(f, "missing {source} `{name}`"),
+            Self::TooLarge {
+                source,
+                name: Some(name),
+                limit,
+            } => write!(f, "{source} `{name}` exceeds {limit} bytes"),
+            Self::TooLarge { source, limit, .. } => {
+                write!(f, "{source} body exceeds {limit} bytes")
+            }
+            Self::UnsupportedMediaType {
+                content_type,
+                accept,
+            } => {
+                match content_type {
+                    Some(content_type) => write!(f, "unsupported content type `{content_type}`")?,
+                    None => f.write_str("missing content type")?,
+                }
+                let accept: Vec<_> = accept.iter().map(BodyKind::essence).collect();
+                write!(f, ", expected one of: {}", accept.join(", "))
+            }
+            Self::UnsupportedCharset {
+                content_type,
+                charset,
+            } => write!(
+                f,
+                "unsupported charset `{charset}` in content type `{content_type}`, only utf-8 is accepted"
+            ),
+            Self::Validation(errors) => {
+                let errors: Vec<_> = errors.iter().map(ValidationError::to_string).collect();
+                write!(f, "validation failed: {}", errors.join("; "))
+            }
+            Self::Conflict {
+                field,
+                first: (first_source, first_name),
+                second: (second_source, second_name),
+            } => write!(
+                f,
+                "conflicting values for `{field}` in {first_source} `{first_name}` and \
+                 {second_source} `{second_name}`"
+            ),
+        }
+    }
+}
+
+impl Error for MappingRejection {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Body(e) => Some(e),
+            Self::Invalid { error, .. } => Some(error.as_ref()),
+            Self::Missing { .. }
+            | Self::TooLarge { .. }
+            | Self::UnsupportedMediaType { .. }
+            | Self::UnsupportedCharset { .. }
+            | Self::Validation(_)
+            | Self::Conflict { .. } => None,
+        }
+    }
+}
+
+impl From<ExtractBodyError> for MappingRejection {
+    fn from(e: ExtractBodyError) -> Self {
+        Self::Body(e)
+    }
+}
+
+#[derive(Serialize)]
+struct RejectionBody {
+    code: &'static str,
+    source: Option<&'static str>,
+    name: Option<String>,
+    field: Option<&'static str>,
+    value: Option<String>,
+    message: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    errors: Vec<ValidationErrorBody>,
+}
+
+#[derive(Serialize)]
+struct ValidationErrorBody {
+    source: &'static str,
+    name: &'static str,
+    field: &'static str,
+    message: String,
+}
+
+impl IntoResponse for MappingRejection {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        let body = match &self {
+            Self::Body(e) => RejectionBody {
+                code: self.code(),
+                source: None,
+                name: None,
+                field: None,
+                value: None,
+                message: e.to_string(),
+                errors: Vec::new(),
+            },
+            Self::Invalid {
+                source,
+                name,
+                field,
+                value,
+                error,
+            } => RejectionBody {
+                code: self.code(),
+                source: Some(source.as_str()),
+                name: name.map(str::to_owned),
+                field: *field,
+                value: value.clone(),
+                message: error.to_string(),
+                errors: Vec::new(),
+            },
+            Self::Missing {
+                source,
+                name,
+                field,
+            } => RejectionBody {
+                code: self.code(),
+                source: Some(source.as_str()),
+                name: Some(name.to_string()),
+                field: Some(field),
+                value: None,
+                message: self.to_string(),
+                errors: Vec::new(),
+            },
+            Self::TooLarge { source, name, .. } => RejectionBody {
+                code: self.code(),
+                source: Some(source.as_str()),
+                name: name.clone(),
+                field: None,
+                value: None,
+                message: self.to_string(),
+                errors: Vec::new(),
+            },
+            Self::UnsupportedMediaType { content_type, .. } => RejectionBody {
+                code: self.code(),
+                source: None,
+                name: None,
+                field: None,
+                value: content_type.clone(),
+                message: self.to_string(),
+                errors: Vec::new(),
+            },
+            Self::UnsupportedCharset { content_type, .. } => RejectionBody {
+                code: self.code(),
+                source: None,
+                name: None,
+                field: None,
+                value: Some(content_type.clone()),
+                message: self.to_string(),
+                errors: Vec::new(),
+            },
+            Self::Validation(errors) => RejectionBody {
+                code: self.code(),
+                source: None,
+                name: None,
+                field: None,
+                value: None,
+                message: self.to_string(),
+                errors: errors
+                    .iter()
+                    .map(|e| ValidationErrorBody {
+                        source: e.source.as_str(),
+                        name: e.name,
+                        field: e.field,
+                        message: e.message.clone(),
+                    })
+                    .collect(),
+            },
+            Self::Conflict {
+                field,
+                first: (source, name),
+                ..
+            } => RejectionBody {
+                code: self.code(),
+                source: Some(source.as_str()),
+                name: Some(name.to_string()),
+                field: Some(field),
+                value: None,
+                message: self.to_string(),
+                errors: Vec::new(),
+            },
+        };
+        (status, Json(body)).into_response()
+    }
+}
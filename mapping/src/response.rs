@@ -0,0 +1,168 @@
+//! Response assembly for `#[derive(IntoResponseMapping)]`.
+//!
+//! Fields use the same attributes as extractor fields: `#[header]` fields become response headers,
+//! `#[cookie]` fields `Set-Cookie` headers and `#[json]` or `#[form]` fields the body. A
+//! `#[status]` field sets the status code, which otherwise defaults to `200 OK`.
+
+use std::{error::Error, fmt, fmt::Display};
+
+use volo_http::{
+    body::Body,
+    error::BoxError,
+    http::{
+        HeaderMap, HeaderName, HeaderValue, StatusCode,
+        header::{CONTENT_TYPE, SET_COOKIE},
+    },
+    response::Response,
+    server::IntoResponse,
+};
+
+use crate::rejection::Source;
+
+/// The parts of a response collected from a struct's fields.
+#[derive(Debug, Default)]
+pub struct ResponseWriter {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Option<(&'static str, Vec<u8>)>,
+}
+
+impl ResponseWriter {
+    /// Run `write` on a fresh writer; a field that cannot be written turns the whole response
+    /// into a `500 Internal Server Error`.
+    pub fn build<F>(write: F) -> Response
+    where
+        F: FnOnce(&mut Self) -> Result<(), ResponseError>,
+    {
+        let mut writer = Self::default();
+        match write(&mut writer) {
+            Ok(()) => writer.finish(),
+            Err(e) => e.into_response(),
+        }
+    }
+
+    pub fn status<S: Into<StatusCode>>(&mut self, status: S) {
+        self.status = status.into();
+    }
+
+    /// Append a header; collection fields call this once per value.
+    pub fn header<T: Display + ?Sized>(
+        &mut self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<(), ResponseError> {
+        let header_name = HeaderName::try_from(name)
+            .map_err(|e| ResponseError::invalid(Source::Header, name, e))?;
+        let value = HeaderValue::try_from(value.to_string())
+            .map_err(|e| ResponseError::invalid(Source::Header, name, e))?;
+        self.headers.append(header_name, value);
+        Ok(())
+    }
+
+    /// Append a `Set-Cookie: name=value` header.
+    pub fn cookie<T: Display + ?Sized>(
+        &mut self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<(), ResponseError> {
+        let value = value.to_string();
+        if value.contains([';', '"']) || value.chars().any(char::is_control) {
+            return Err(ResponseError::invalid(
+                Source::Cookie,
+                name,
+                "cookie values cannot contain `;`, `\"` or control characters",
+            ));
+        }
+        let cookie = HeaderValue::try_from(format!("{name}={value}"))
+            .map_err(|e| ResponseError::invalid(Source::Cookie, name, e))?;
+        self.headers.append(SET_COOKIE, cookie);
+        Ok(())
+    }
+
+    /// Set the encoded body and its `Content-Type`.
+    pub fn body(&mut self, content_type: &'static str, bytes: Vec<u8>) {
+        self.body = Some((content_type, bytes));
+    }
+
+    pub fn finish(mut self) -> Response {
+        let body = match self.body {
+            Some((content_type, bytes)) => {
+                self.headers
+                    .insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+                Body::from(bytes)
+            }
+            None => Body::empty(),
+        };
+        let mut response = Response::new(body);
+        *response.status_mut() = self.status;
+        *response.headers_mut() = self.headers;
+        response
+    }
+}
+
+/// A field could not be written to the response.
+#[derive(Debug)]
+pub enum ResponseError {
+    /// A value is not valid in its place, e.g. a header value with a newline.
+    Invalid {
+        source: Source,
+        name: &'static str,
+        error: BoxError,
+    },
+    /// The body failed to encode.
+    Body { source: Source, error: BoxError },
+}
+
+impl ResponseError {
+    pub fn invalid<E>(source: Source, name: &'static str, error: E) -> Self
+    where
+        E: Into<BoxError>,
+    {
+        Self::Invalid {
+            source,
+            name,
+            error: error.into(),
+        }
+    }
+
+    pub fn body<E>(source: Source, error: E) -> Self
+    where
+        E: Into<BoxError>,
+    {
+        Self::Body {
+            source,
+            error: error.into(),
+        }
+    }
+}
+
+impl Display for ResponseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Invalid {
+                source,
+                name,
+                error,
+            } => write!(f, "invalid response {source} `{name}`: {error}"),
+            Self::Body { source, error } => {
+                write!(f, "failed to encode {source} response body: {error}")
+            }
+        }
+    }
+}
+
+impl Error for ResponseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Invalid { error, .. } | Self::Body { error, .. } => Some(error.as_ref()),
+        }
+    }
+}
+
+impl IntoResponse for ResponseError {
+    fn into_response(self) -> Response {
+        let mut response = Response::new(Body::from(self.to_string()));
+        *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+        response
+    }
+}
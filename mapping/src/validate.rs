@@ -0,0 +1,203 @@
+//! Checks behind `#[validate(...)]` field attributes.
+//!
+//! Each check returns `Err(message)` on failure; the generated extractor collects every failure
+//! into a single [`MappingRejection::Validation`](crate::MappingRejection::Validation).
+//! `Option` fields are only checked when present, `Vec` fields have `range`, `regex` and
+//! `email` applied to every item, and [`Sourced`] fields are checked on their value.
+
+use std::fmt::Display;
+
+pub use regex::Regex;
+
+use crate::Sourced;
+
+/// Values that `#[validate(range(...))]` can bound.
+pub trait ValidateRange {
+    type Value: PartialOrd + Display;
+
+    /// Whether `f` holds for every value present.
+    fn all_values(&self, f: &mut dyn FnMut(&Self::Value) -> bool) -> bool;
+}
+
+macro_rules! impl_validate_range {
+    ($($ty:ty),*) => {
+        $(
+            impl ValidateRange for $ty {
+                type Value = $ty;
+
+                fn all_values(&self, f: &mut dyn FnMut(&Self::Value) -> bool) -> bool {
+                    f(self)
+                }
+            }
+        )*
+    };
+}
+
+impl_validate_range!(
+    i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64
+);
+
+impl<T: ValidateRange> ValidateRange for Option<T> {
+    type Value = T::Value;
+
+    fn all_values(&self, f: &mut dyn FnMut(&Self::Value) -> bool) -> bool {
+        self.as_ref().is_none_or(|v| v.all_values(f))
+    }
+}
+
+impl<T: ValidateRange> ValidateRange for Sourced<T> {
+    type Value = T::Value;
+
+    fn all_values(&self, f: &mut dyn FnMut(&Self::Value) -> bool) -> bool {
+        self.value.all_values(f)
+    }
+}
+
+impl<T: ValidateRange> ValidateRange for Vec<T> {
+    type Value = T::Value;
+
+    fn all_values(&self, f: &mut dyn FnMut(&Self::Value) -> bool) -> bool {
+        self.iter().all(|v| v.all_values(f))
+    }
+}
+
+/// Values that `#[validate(length(...))]` can measure; strings count chars.
+pub trait ValidateLength {
+    /// `None` when the value is absent and should not be checked.
+    fn length(&self) -> Option<usize>;
+}
+
+impl ValidateLength for String {
+    fn length(&self) -> Option<usize> {
+        Some(self.chars().count())
+    }
+}
+
+impl<T> ValidateLength for Vec<T> {
+    fn length(&self) -> Option<usize> {
+        Some(self.len())
+    }
+}
+
+impl<T: ValidateLength> ValidateLength for Option<T> {
+    fn length(&self) -> Option<usize> {
+        self.as_ref().and_then(ValidateLength::length)
+    }
+}
+
+impl<T: ValidateLength> ValidateLength for Sourced<T> {
+    fn length(&self) -> Option<usize> {
+        self.value.length()
+    }
+}
+
+/// Values that `#[validate(regex = "...")]` and `#[validate(email)]` can match.
+pub trait ValidateStr {
+    /// Whether `f` holds for every string present.
+    fn all_strs(&self, f: &mut dyn FnMut(&str) -> bool) -> bool;
+}
+
+impl ValidateStr for String {
+    fn all_strs(&self, f: &mut dyn FnMut(&str) -> bool) -> bool {
+        f(self)
+    }
+}
+
+impl<T: ValidateStr> ValidateStr for Option<T> {
+    fn all_strs(&self, f: &mut dyn FnMut(&str) -> bool) -> bool {
+        self.as_ref().is_none_or(|v| v.all_strs(f))
+    }
+}
+
+impl<T: ValidateStr> ValidateStr for Sourced<T> {
+    fn all_strs(&self, f: &mut dyn FnMut(&str) -> bool) -> bool {
+        self.value.all_strs(f)
+    }
+}
+
+impl<T: ValidateStr> ValidateStr for Vec<T> {
+    fn all_strs(&self, f: &mut dyn FnMut(&str) -> bool) -> bool {
+        self.iter().all(|v| v.all_strs(f))
+    }
+}
+
+pub fn range<V>(value: &V, min: Option<V::Value>, max: Option<V::Value>) -> Result<(), String>
+where
+    V: ValidateRange + ?Sized,
+{
+    let in_range = value.all_values(&mut |v| {
+        min.as_ref().is_none_or(|min| v >= min) && max.as_ref().is_none_or(|max| v <= max)
+    });
+    if in_range {
+        return Ok(());
+    }
+    Err(match (min, max) {
+        (Some(min), Some(max)) => format!("must be between {min} and {max}"),
+        (Some(min), None) => format!("must be at least {min}"),
+        (None, Some(max)) => format!("must be at most {max}"),
+        (None, None) => unreachable!("a range without bounds always holds"),
+    })
+}
+
+pub fn length<V>(value: &V, min: Option<usize>, max: Option<usize>) -> Result<(), String>
+where
+    V: ValidateLength + ?Sized,
+{
+    let Some(len) = value.length() else {
+        return Ok(());
+    };
+    if min.is_none_or(|min| len >= min) && max.is_none_or(|max| len <= max) {
+        return Ok(());
+    }
+    Err(match (min, max) {
+        (Some(min), Some(max)) if min == max => format!("length must be {min}"),
+        (Some(min), Some(max)) => format!("length must be between {min} and {max}"),
+        (Some(min), None) => format!("length must be at least {min}"),
+        (None, Some(max)) => format!("length must be at most {max}"),
+        (None, None) => unreachable!("a length without bounds always holds"),
+    })
+}
+
+pub fn regex<V>(value: &V, regex: &Regex) -> Result<(), String>
+where
+    V: ValidateStr + ?Sized,
+{
+    if value.all_strs(&mut |s| regex.is_match(s)) {
+        Ok(())
+    } else {
+        Err(format!("must match `{}`", regex.as_str()))
+    }
+}
+
+pub fn email<V>(value: &V) -> Result<(), String>
+where
+    V: ValidateStr + ?Sized,
+{
+    if value.all_strs(&mut is_email) {
+        Ok(())
+    } else {
+        Err("must be a valid email address".to_owned())
+    }
+}
+
+/// A deliberately loose check: one `@`, a non-empty local part and a dotted domain.
+fn is_email(s: &str) -> bool {
+    let Some((local, domain)) = s.split_once('@') else {
+        return false;
+    };
+    !local.is_empty()
+        && !domain.contains('@')
+        && !s.chars().any(char::is_whitespace)
+        && domain
+            .split_once('.')
+            .is_some_and(|(name, tld)| !name.is_empty() && !tld.is_empty())
+        && !domain.ends_with('.')
+}
+
+/// Run a `#[validate(custom = path::to::fn)]` check.
+pub fn custom<E>(result: Result<(), E>) -> Result<(), String>
+where
+    E: Display,
+{
+    result.map_err(|e| e.to_string())
+}
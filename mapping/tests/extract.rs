@@ -0,0 +1,1935 @@
+use std::{
+    borrow::Cow,
+    collections::{BTreeSet, HashSet},
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    num::NonZeroU64,
+    sync::Arc,
+};
+
+use mapping::{IntoRequest, Mapping, Sourced, UploadFile, codec::BodyCodec};
+use smallvec::SmallVec;
+use sonic_rs::{JsonContainerTrait, JsonValueTrait};
+use volo::{Service, net::Address};
+use volo_http::{
+    body::{Body, BodyConversion},
+    context::ServerContext,
+    http::{HeaderValue, Method, StatusCode, header},
+    request::Request,
+    response::Response,
+    server::{Router, route::post},
+};
+
+#[derive(Mapping, Debug)]
+#[mapping(path = "/hostile/{pid}/{cids}")]
+pub struct HostileParam {
+    #[header("x-token")]
+    pub token: Option<i64>,
+    #[header]
+    pub ids: Vec<i64>,
+    #[uri]
+    pub pid: i64,
+    #[uri]
+    pub cids: Vec<i64>,
+    #[query]
+    #[serde(default)]
+    pub page: u32,
+    #[json]
+    #[serde(default)]
+    pub name: String,
+}
+
+#[derive(Mapping, Debug)]
+#[mapping(lenient)]
+pub struct LenientParam {
+    #[header("x-token")]
+    pub token: Option<i64>,
+    #[header]
+    pub ids: Vec<i64>,
+    #[uri]
+    pub pid: i64,
+}
+
+#[derive(Mapping, Debug)]
+#[format = "form"]
+pub struct FormParam {
+    #[serde(default)]
+    pub uid: i64,
+}
+
+#[derive(Mapping, Debug)]
+pub struct BodyParam {
+    #[json]
+    #[serde(default)]
+    pub name: String,
+    #[form]
+    #[serde(default)]
+    pub uid: i64,
+}
+
+#[derive(Mapping, Debug)]
+#[mapping(accept = ["json"])]
+pub struct JsonOnlyParam {
+    #[json]
+    #[serde(default)]
+    pub name: String,
+    #[form]
+    #[serde(default)]
+    pub uid: i64,
+}
+
+#[derive(Mapping, Debug)]
+pub struct CodecParam {
+    #[msgpack]
+    #[serde(default)]
+    pub name: String,
+    #[cbor]
+    #[serde(default)]
+    pub uid: i64,
+}
+
+#[derive(Mapping, Debug)]
+#[format = "xml"]
+pub struct XmlParam {
+    #[serde(rename = "Name")]
+    pub name: String,
+    #[serde(default)]
+    pub uid: i64,
+    #[query]
+    #[serde(default)]
+    pub page: u32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Greeting {
+    #[prost(string, tag = "1")]
+    pub name: String,
+}
+
+#[derive(Mapping, Debug)]
+pub struct ProtobufParam {
+    #[protobuf]
+    pub greeting: Greeting,
+}
+
+pub struct SonicCodec;
+
+impl BodyCodec for SonicCodec {
+    const NAME: &'static str = "sonic";
+    const MEDIA_TYPES: &'static [&'static str] = &["application/x-sonic"];
+
+    fn decode<T>(bytes: &[u8]) -> Result<T, volo_http::error::BoxError>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        Ok(sonic_rs::from_slice(bytes)?)
+    }
+}
+
+#[derive(Mapping, Debug)]
+pub struct CustomCodecParam {
+    #[body(codec = SonicCodec)]
+    pub name: String,
+}
+
+#[derive(Mapping, Debug)]
+#[format = "query"]
+pub struct Pagination {
+    #[serde(default)]
+    pub page: u32,
+    #[serde(default)]
+    pub size: u32,
+}
+
+#[derive(Mapping, Debug)]
+pub struct Auth {
+    #[header("x-token")]
+    pub token: String,
+    #[ext]
+    pub user_id: Option<i64>,
+}
+
+#[derive(Mapping, Debug)]
+pub struct FlattenParam {
+    #[mapping(flatten)]
+    pub paging: Pagination,
+    #[mapping(flatten)]
+    pub auth: Auth,
+    #[json]
+    pub name: String,
+}
+
+fn not_reserved(name: &String) -> Result<(), &'static str> {
+    if name == "admin" {
+        Err("is reserved")
+    } else {
+        Ok(())
+    }
+}
+
+#[derive(Mapping, Debug)]
+pub struct ValidatedParam {
+    #[header("x-page")]
+    #[validate(range(min = 1, max = 100))]
+    pub page: u32,
+    #[uri]
+    #[validate(length(min = 2, max = 8))]
+    pub slug: String,
+    #[query]
+    #[serde(default)]
+    #[validate(email)]
+    pub email: Option<String>,
+    #[json]
+    #[serde(default)]
+    #[validate(regex = "^[a-z]*$", custom = not_reserved)]
+    pub name: String,
+    #[form]
+    #[serde(default)]
+    #[validate(range(max = 10))]
+    pub uid: i64,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct Item {
+    pub name: String,
+}
+
+#[derive(Mapping, Debug)]
+pub struct Paged<T> {
+    #[json]
+    pub body: T,
+    #[query]
+    #[serde(default)]
+    pub page: u32,
+}
+
+#[derive(Mapping, Debug)]
+pub struct TypedHeaders<T, const MAX: usize>
+where
+    T: std::fmt::Debug,
+{
+    #[header]
+    pub ids: Vec<T>,
+    #[header("x-limit")]
+    pub limit: Option<T>,
+    #[query]
+    #[serde(default)]
+    pub tags: Vec<T>,
+}
+
+#[derive(Mapping, Debug)]
+pub struct Borrowed<'a> {
+    #[json]
+    pub name: Cow<'a, str>,
+}
+
+#[derive(Mapping, Debug)]
+#[mapping(tag = header("x-api-version"))]
+pub enum VersionedParam {
+    #[mapping(rename = "1")]
+    V1 {
+        #[json]
+        name: String,
+    },
+    #[mapping(rename = "2")]
+    V2 {
+        #[json]
+        full_name: String,
+        #[query]
+        #[serde(default)]
+        page: u32,
+    },
+}
+
+#[derive(Mapping, Debug)]
+#[mapping(tag = uri("version"))]
+pub enum PathVersioned {
+    #[mapping(rename = "v1")]
+    V1 {
+        #[header("x-token")]
+        token: String,
+    },
+    #[mapping(rename = "ping")]
+    Ping,
+}
+
+#[derive(Mapping, Debug)]
+#[mapping(tag = json("type"))]
+pub enum Shape {
+    #[mapping(rename = "circle")]
+    Circle { radius: f64 },
+    #[mapping(rename = "rect")]
+    Rect {
+        #[validate(range(min = 1.0, max = 100.0))]
+        w: f64,
+        #[validate(range(min = 1.0, max = 100.0))]
+        h: f64,
+    },
+}
+
+#[derive(Mapping, Debug)]
+pub struct RequiredParam {
+    #[header("x-token")]
+    pub token: String,
+    #[header(default)]
+    pub ids: Vec<i64>,
+    #[header("x-page", default = "7")]
+    pub page: u32,
+    #[ext]
+    pub user_id: Option<i64>,
+}
+
+#[derive(Mapping, Debug)]
+pub struct ExtParam {
+    #[ext]
+    pub user_id: i64,
+}
+
+#[derive(Mapping, Debug, Clone, PartialEq)]
+#[mapping(client)]
+pub struct ClientPaging {
+    #[query]
+    pub offset: u32,
+    #[header("x-trace")]
+    pub trace: Option<String>,
+}
+
+#[derive(Mapping, Debug, Clone, PartialEq)]
+#[mapping(client, path = "/client/{pid}/{cids}")]
+pub struct ClientParam {
+    #[uri]
+    pub pid: i64,
+    #[uri]
+    pub cids: Vec<i64>,
+    #[header("x-token")]
+    pub token: Option<String>,
+    #[header]
+    pub ids: Vec<i64>,
+    #[query(style = "comma")]
+    pub tags: Vec<String>,
+    #[query]
+    pub page: Option<u32>,
+    #[cookie]
+    pub session: String,
+    #[mapping(from(header("x-api-key"), query("api_key")))]
+    pub api_key: Sourced<String>,
+    #[mapping(flatten)]
+    pub paging: ClientPaging,
+    #[ext]
+    pub user_id: Option<i64>,
+    #[json]
+    pub title: String,
+    #[json]
+    #[serde(default)]
+    pub labels: Vec<String>,
+}
+
+#[derive(Mapping, Debug, Clone, PartialEq)]
+#[mapping(client)]
+pub struct ClientForm {
+    #[query(style = "brackets")]
+    pub tags: Vec<String>,
+    #[form]
+    pub name: String,
+    #[form]
+    pub age: Option<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CurrentUser {
+    pub name: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Tenant(pub String);
+
+/// Not `Clone`, so it can only be bound with `#[ext(take)]`.
+#[derive(Debug)]
+pub struct TraceId(pub String);
+
+#[derive(Mapping, Debug)]
+pub struct AuthParam {
+    #[ext]
+    pub user: CurrentUser,
+    #[ext]
+    pub roles: Arc<Vec<String>>,
+    #[ext]
+    pub scopes: Vec<String>,
+    #[ext]
+    pub tenant: Option<Tenant>,
+    #[ext(take)]
+    pub trace: TraceId,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+    Book,
+    Movie,
+}
+
+#[derive(Mapping, Debug)]
+pub struct NoDefaultParam {
+    #[header("x-id")]
+    pub id: NonZeroU64,
+    #[query]
+    pub kind: Kind,
+    #[json]
+    pub title: String,
+}
+
+#[derive(Mapping, Debug)]
+pub struct CookieParam {
+    #[cookie("sid")]
+    pub session: String,
+    #[cookie]
+    pub csrf: Option<String>,
+    #[cookie(default)]
+    pub ids: Vec<u32>,
+}
+
+#[derive(Mapping, Debug)]
+#[format = "multipart"]
+#[mapping(multipart(part_limit = 64))]
+pub struct UploadParam {
+    pub title: String,
+    #[serde(default)]
+    pub count: u32,
+    pub avatar: UploadFile,
+    #[multipart(limit = 8)]
+    pub note: Option<UploadFile>,
+    #[multipart(default)]
+    pub attachments: Vec<UploadFile>,
+}
+
+#[derive(Mapping, Debug)]
+#[mapping(header(rename_all = "kebab-case"), query(rename_all = "camelCase"))]
+pub struct RenamedParam {
+    #[header]
+    pub request_id: String,
+    #[header("x-trace")]
+    pub trace_id: Option<String>,
+    #[query]
+    pub page_size: u32,
+    #[query]
+    #[serde(rename = "sort")]
+    pub sort_by: Option<String>,
+    #[query("q")]
+    pub search_term: Option<String>,
+}
+
+#[derive(Mapping, Debug)]
+pub struct MultiValueParam {
+    #[query]
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[query(style = "comma")]
+    #[serde(default)]
+    pub ids: Vec<u32>,
+    #[query(style = "brackets")]
+    pub sort: Option<Vec<String>>,
+    #[query]
+    pub page: Option<u32>,
+    #[header("x-role", default)]
+    pub roles: Vec<String>,
+}
+
+#[derive(Mapping, Debug)]
+pub struct ListParam {
+    #[header(delimiter = ";")]
+    pub ids: Vec<u32>,
+    #[header("x-tags")]
+    pub tags: HashSet<String>,
+    #[header("x-sorted", default)]
+    pub sorted: BTreeSet<u32>,
+    #[header("x-small", default)]
+    pub small: SmallVec<[u16; 4]>,
+    #[header("x-pair")]
+    pub pair: Option<[i32; 2]>,
+    #[uri(delimiter = "|")]
+    pub rgb: [u8; 3],
+}
+
+#[derive(Debug)]
+pub struct ApiVersion(pub u8);
+
+fn parse_api_version(s: &str) -> Result<ApiVersion, String> {
+    s.strip_prefix('v')
+        .and_then(|n| n.parse().ok())
+        .map(ApiVersion)
+        .ok_or_else(|| format!("`{s}` is not a version like v2"))
+}
+
+/// A `<id>.sig` token standing in for a signed pagination cursor.
+#[derive(Debug)]
+pub struct Cursor(pub u64);
+
+impl TryFrom<String> for Cursor {
+    type Error = String;
+
+    fn try_from(token: String) -> Result<Self, Self::Error> {
+        match token.split_once('.') {
+            Some((id, "sig")) => id.parse().map(Cursor).map_err(|e| format!("{e}")),
+            _ => Err("cursor signature mismatch".to_owned()),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct PageNo(pub u32);
+
+impl TryFrom<u32> for PageNo {
+    type Error = &'static str;
+
+    fn try_from(page: u32) -> Result<Self, Self::Error> {
+        if page == 0 {
+            return Err("pages start at 1");
+        }
+        Ok(PageNo(page))
+    }
+}
+
+#[derive(Mapping, Debug)]
+pub struct ConvertParam {
+    #[header("x-api-version", with = "parse_api_version")]
+    pub version: ApiVersion,
+    #[uri(parse_with = parse_api_version)]
+    pub min: ApiVersion,
+    #[query]
+    #[mapping(try_from = "String")]
+    pub after: Option<Cursor>,
+    #[json]
+    #[mapping(try_from = "String")]
+    pub cursor: Cursor,
+    #[header("x-page")]
+    #[mapping(try_from = "u32")]
+    pub page: PageNo,
+}
+
+#[derive(Mapping, Debug)]
+pub struct FallbackParam {
+    #[mapping(from(header("x-api-key"), query("api_key"), cookie))]
+    #[validate(length(max = 8))]
+    pub api_key: Sourced<String>,
+    #[mapping(from(uri, query), conflict = "reject")]
+    pub page: Option<u32>,
+    #[mapping(from(header("x-tags"), query))]
+    pub tags: Option<Vec<String>>,
+}
+
+/// Field names that mirror the derive's own locals.
+#[derive(Mapping, Debug)]
+pub struct ShadowParam {
+    #[header("x-json-mode")]
+    pub json_mode: String,
+    #[mapping(from(header("x-page"), query))]
+    pub page: u32,
+    #[query]
+    pub page_from: String,
+    #[json]
+    pub name: String,
+}
+
+/// Raw identifiers bind under their unprefixed names.
+#[derive(Mapping, Debug)]
+pub struct RawParam {
+    #[header]
+    pub r#type: String,
+    #[mapping(from(header("x-loop"), query))]
+    pub r#loop: Option<u32>,
+    #[query]
+    #[validate(range(max = 10))]
+    pub r#match: u32,
+    #[json]
+    pub r#ref: String,
+}
+
+async fn hostile(_: HostileParam) -> &'static str {
+    "ok"
+}
+
+async fn lenient(_: LenientParam) -> &'static str {
+    "ok"
+}
+
+async fn form(_: FormParam) -> &'static str {
+    "ok"
+}
+
+async fn body(req: BodyParam) -> String {
+    format!("{} {}", req.name, req.uid)
+}
+
+async fn json_only(req: JsonOnlyParam) -> String {
+    format!("{} {}", req.name, req.uid)
+}
+
+async fn codec(req: CodecParam) -> String {
+    format!("{} {}", req.name, req.uid)
+}
+
+async fn xml(req: XmlParam) -> String {
+    format!("{} {} {}", req.name, req.uid, req.page)
+}
+
+async fn protobuf(req: ProtobufParam) -> String {
+    req.greeting.name
+}
+
+async fn custom_codec(req: CustomCodecParam) -> String {
+    req.name
+}
+
+async fn flatten(req: FlattenParam) -> String {
+    format!(
+        "{} {} {} {:?} {}",
+        req.paging.page, req.paging.size, req.auth.token, req.auth.user_id, req.name
+    )
+}
+
+async fn validated(req: ValidatedParam) -> String {
+    format!(
+        "{} {} {:?} {} {}",
+        req.page, req.slug, req.email, req.name, req.uid
+    )
+}
+
+async fn paged(req: Paged<Item>) -> String {
+    format!("{} {}", req.body.name, req.page)
+}
+
+async fn typed_headers(req: TypedHeaders<u8, 2>) -> String {
+    if req.ids.len() > 2 {
+        return "too many".to_owned();
+    }
+    format!("{:?} {:?} {:?}", req.ids, req.limit, req.tags)
+}
+
+async fn borrowed(req: Borrowed<'static>) -> String {
+    req.name.into_owned()
+}
+
+async fn versioned(req: VersionedParam) -> String {
+    match req {
+        VersionedParam::V1 { name } => format!("v1 {name}"),
+        VersionedParam::V2 { full_name, page } => format!("v2 {full_name} {page}"),
+    }
+}
+
+async fn path_versioned(req: PathVersioned) -> String {
+    match req {
+        PathVersioned::V1 { token } => format!("v1 {token}"),
+        PathVersioned::Ping => "ping".to_owned(),
+    }
+}
+
+async fn shape(req: Shape) -> String {
+    match req {
+        Shape::Circle { radius } => format!("circle {radius}"),
+        Shape::Rect { w, h } => format!("rect {w}x{h}"),
+    }
+}
+
+async fn required(req: RequiredParam) -> String {
+    format!("{} {:?} {} {:?}", req.token, req.ids, req.page, req.user_id)
+}
+
+async fn no_default(req: NoDefaultParam) -> String {
+    format!("{} {:?} {}", req.id, req.kind, req.title)
+}
+
+async fn cookie(req: CookieParam) -> String {
+    format!("{} {:?} {:?}", req.session, req.csrf, req.ids)
+}
+
+async fn upload(req: UploadParam) -> String {
+    format!(
+        "{} {} {:?} {:?} {:?} {}",
+        req.title,
+        req.count,
+        req.avatar.file_name(),
+        req.avatar.content_type().map(|m| m.essence_str()),
+        req.avatar.bytes(),
+        req.attachments.len(),
+    )
+}
+
+async fn renamed(req: RenamedParam) -> String {
+    format!(
+        "{} {:?} {} {:?} {:?}",
+        req.request_id, req.trace_id, req.page_size, req.sort_by, req.search_term
+    )
+}
+
+async fn multi_value(req: MultiValueParam) -> String {
+    format!(
+        "{:?} {:?} {:?} {:?} {:?}",
+        req.tags, req.ids, req.sort, req.page, req.roles
+    )
+}
+
+async fn list(req: ListParam) -> String {
+    let mut tags: Vec<_> = req.tags.into_iter().collect();
+    tags.sort();
+    format!(
+        "{:?} {:?} {:?} {:?} {:?} {:?}",
+        req.ids, tags, req.sorted, req.small, req.pair, req.rgb
+    )
+}
+
+async fn convert(req: ConvertParam) -> String {
+    format!(
+        "{:?} {:?} {:?} {:?} {:?}",
+        req.version, req.min, req.after, req.cursor, req.page
+    )
+}
+
+async fn fallback(req: FallbackParam) -> String {
+    format!(
+        "{} {} {} {:?} {:?}",
+        req.api_key.source, req.api_key.name, *req.api_key, req.page, req.tags
+    )
+}
+
+async fn ext(req: ExtParam) -> String {
+    req.user_id.to_string()
+}
+
+async fn shadow(req: ShadowParam) -> String {
+    format!(
+        "{} {} {} {}",
+        req.json_mode, req.page, req.page_from, req.name
+    )
+}
+
+async fn raw(req: RawParam) -> String {
+    format!(
+        "{} {:?} {} {}",
+        req.r#type, req.r#loop, req.r#match, req.r#ref
+    )
+}
+
+async fn client(req: ClientParam) -> String {
+    format!("{req:?}")
+}
+
+async fn client_form(req: ClientForm) -> String {
+    format!("{req:?}")
+}
+
+async fn auth(req: AuthParam) -> String {
+    format!(
+        "{} {:?} {:?} {:?} {}",
+        req.user.name, req.roles, req.scopes, req.tenant, req.trace.0
+    )
+}
+
+fn router() -> Router {
+    Router::new()
+        .route(HostileParam::PATH, post(hostile))
+        .route("/lenient/{pid}", post(lenient))
+        .route("/form", post(form))
+        .route("/body", post(body))
+        .route("/json-only", post(json_only))
+        .route("/flatten", post(flatten))
+        .route("/versioned", post(versioned))
+        .route("/path-versioned/{version}", post(path_versioned))
+        .route("/shape", post(shape))
+        .route("/paged", post(paged))
+        .route("/typed-headers", post(typed_headers))
+        .route("/borrowed", post(borrowed))
+        .route("/validated/{slug}", post(validated))
+        .route("/codec", post(codec))
+        .route("/xml", post(xml))
+        .route("/protobuf", post(protobuf))
+        .route("/custom-codec", post(custom_codec))
+        .route("/required", post(required))
+        .route("/ext", post(ext))
+        .route("/auth", post(auth))
+        .route(ClientParam::PATH, post(client))
+        .route("/client-form", post(client_form))
+        .route("/shadow", post(shadow))
+        .route("/raw", post(raw))
+        .route("/no-default", post(no_default))
+        .route("/cookie", post(cookie))
+        .route("/upload", post(upload))
+        .route("/renamed", post(renamed))
+        .route("/multi-value", post(multi_value))
+        .route("/list/{rgb}", post(list))
+        .route("/convert/{min}", post(convert))
+        .route("/fallback", post(fallback))
+        .route("/fallback/{page}", post(fallback))
+}
+
+async fn call(req: Request) -> Response {
+    let addr = Address::from(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 8000));
+    let mut cx = ServerContext::new(addr);
+    router().call(&mut cx, req).await.unwrap()
+}
+
+/// Like [`call`], with extensions inserted into the context first.
+async fn call_with(req: Request, extend: impl FnOnce(&mut ServerContext)) -> Response {
+    let addr = Address::from(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 8000));
+    let mut cx = ServerContext::new(addr);
+    extend(&mut cx);
+    router().call(&mut cx, req).await.unwrap()
+}
+
+fn request(uri: &str) -> volo_http::http::request::Builder {
+    Request::builder().method("POST").uri(uri)
+}
+
+async fn rejection(resp: Response) -> sonic_rs::Value {
+    let body = resp.into_body().into_bytes().await.unwrap();
+    sonic_rs::from_slice(&body).unwrap()
+}
+
+#[tokio::test]
+async fn accepts_well_formed_request() {
+    let req = request("/hostile/1/2,3?page=2")
+        .header("x-token", "42")
+        .header("ids", "1,2")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(r#"{"name":"volo"}"#))
+        .unwrap();
+    assert_eq!(call(req).await.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn rejects_non_visible_ascii_header() {
+    let req = request("/hostile/1/2")
+        .header("x-token", HeaderValue::from_bytes(b"\xff\xfe").unwrap())
+        .body(Body::empty())
+        .unwrap();
+    let resp = call(req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let body = rejection(resp).await;
+    assert_eq!(body["source"].as_str(), Some("header"));
+    assert_eq!(body["name"].as_str(), Some("x-token"));
+    assert_eq!(body["field"].as_str(), Some("token"));
+}
+
+#[tokio::test]
+async fn rejects_bad_list_item() {
+    let req = request("/hostile/1/2")
+        .header("ids", "1,abc,3")
+        .body(Body::empty())
+        .unwrap();
+    let resp = call(req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let body = rejection(resp).await;
+    assert_eq!(body["field"].as_str(), Some("ids"));
+    assert_eq!(body["value"].as_str(), Some("abc"));
+}
+
+#[tokio::test]
+async fn rejects_bad_path_segment() {
+    let req = request("/hostile/%ZZ/2,x").body(Body::empty()).unwrap();
+    let resp = call(req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(rejection(resp).await["source"].as_str(), Some("uri"));
+}
+
+#[tokio::test]
+async fn rejects_broken_query_string() {
+    for query in ["page=%FF%FE", "page=1&page=2", "page=-1", "page="] {
+        let req = request(&format!("/hostile/1/2?{query}"))
+            .body(Body::empty())
+            .unwrap();
+        let resp = call(req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "query: {query}");
+        assert_eq!(rejection(resp).await["source"].as_str(), Some("query"));
+    }
+}
+
+#[tokio::test]
+async fn rejects_malformed_json() {
+    for payload in ["{", r#"{"name":1}"#, "\u{0}", r#"{"name":"a","name":"b"}"#] {
+        let req = request("/hostile/1/2")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(payload))
+            .unwrap();
+        let resp = call(req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "payload: {payload}");
+        assert_eq!(rejection(resp).await["source"].as_str(), Some("json"));
+    }
+}
+
+#[tokio::test]
+async fn rejects_malformed_form() {
+    for payload in ["uid=abc", "uid=1&uid=2", "uid=%FF"] {
+        let req = request("/form")
+            .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(Body::from(payload))
+            .unwrap();
+        let resp = call(req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "payload: {payload}");
+        assert_eq!(rejection(resp).await["source"].as_str(), Some("form"));
+    }
+}
+
+#[tokio::test]
+async fn lenient_ignores_hostile_values() {
+    let req = request("/lenient/abc")
+        .header("x-token", HeaderValue::from_bytes(b"\xff").unwrap())
+        .header("ids", "1,abc,3")
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(call(req).await.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn rejects_missing_required_header() {
+    let resp = call(request("/required").body(Body::empty()).unwrap()).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let body = rejection(resp).await;
+    assert_eq!(body["code"].as_str(), Some("missing_field"));
+    assert_eq!(body["name"].as_str(), Some("x-token"));
+    assert_eq!(body["message"].as_str(), Some("missing header `x-token`"));
+}
+
+#[tokio::test]
+async fn falls_back_to_declared_defaults() {
+    let req = request("/required")
+        .header("x-token", "abc")
+        .body(Body::empty())
+        .unwrap();
+    let resp = call(req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body = resp.into_body().into_string().await.unwrap();
+    assert_eq!(body, "abc [] 7 None");
+}
+
+#[tokio::test]
+async fn rejects_missing_extension_as_server_error() {
+    let resp = call(request("/ext").body(Body::empty()).unwrap()).await;
+    assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    let body = rejection(resp).await;
+    assert_eq!(body["source"].as_str(), Some("ext"));
+    assert_eq!(body["code"].as_str(), Some("missing_extension"));
+}
+
+#[tokio::test]
+async fn binds_cloned_and_taken_extensions() {
+    let resp = call_with(request("/auth").body(Body::empty()).unwrap(), |cx| {
+        cx.extensions.insert(CurrentUser {
+            name: "volo".to_owned(),
+        });
+        cx.extensions.insert(Arc::new(vec!["admin".to_owned()]));
+        cx.extensions
+            .insert(vec!["read".to_owned(), "write".to_owned()]);
+        cx.extensions.insert(TraceId("t-1".to_owned()));
+    })
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(
+        body_text(resp).await,
+        r#"volo ["admin"] ["read", "write"] None t-1"#
+    );
+
+    // A required extension that is absent rejects instead of defaulting
+    let resp = call_with(request("/auth").body(Body::empty()).unwrap(), |cx| {
+        cx.extensions.insert(CurrentUser {
+            name: "volo".to_owned(),
+        });
+        cx.extensions.insert(Arc::new(Vec::<String>::new()));
+        cx.extensions.insert(Vec::<String>::new());
+        cx.extensions.insert(Tenant("acme".to_owned()));
+    })
+    .await;
+    assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    let body = rejection(resp).await;
+    assert_eq!(body["code"].as_str(), Some("missing_extension"));
+    assert_eq!(body["field"].as_str(), Some("trace"));
+}
+
+#[tokio::test]
+async fn binds_struct_without_default() {
+    let req = request("/no-default?kind=movie")
+        .header("x-id", "7")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(r#"{"title":"volo"}"#))
+        .unwrap();
+    let resp = call(req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body = resp.into_body().into_string().await.unwrap();
+    assert_eq!(body, "7 Movie volo");
+}
+
+#[tokio::test]
+async fn rejects_missing_serde_fields() {
+    let req = request("/no-default")
+        .header("x-id", "7")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(r#"{"title":"volo"}"#))
+        .unwrap();
+    let resp = call(req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(rejection(resp).await["source"].as_str(), Some("query"));
+
+    let req = request("/no-default?kind=book")
+        .header("x-id", "0")
+        .body(Body::empty())
+        .unwrap();
+    let resp = call(req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(rejection(resp).await["source"].as_str(), Some("json"));
+}
+
+#[tokio::test]
+async fn binds_cookies_across_headers() {
+    let req = request("/cookie")
+        .header(header::COOKIE, "theme=dark; sid=\"abc\"; ids=1,2")
+        .header(header::COOKIE, "csrf=t0k; sid=shadowed")
+        .body(Body::empty())
+        .unwrap();
+    let resp = call(req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body = resp.into_body().into_string().await.unwrap();
+    assert_eq!(body, r#"abc Some("t0k") [1, 2]"#);
+
+    let req = request("/cookie")
+        .header(header::COOKIE, "sid=a; ids=1; ids=2")
+        .header(header::COOKIE, "ids=3,4")
+        .body(Body::empty())
+        .unwrap();
+    let body = body_text(call(req).await).await;
+    assert_eq!(body, "a None [1, 2, 3, 4]");
+}
+
+#[tokio::test]
+async fn rejects_missing_or_invalid_cookie() {
+    let resp = call(request("/cookie").body(Body::empty()).unwrap()).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let body = rejection(resp).await;
+    assert_eq!(body["code"].as_str(), Some("missing_field"));
+    assert_eq!(body["name"].as_str(), Some("sid"));
+
+    let req = request("/cookie")
+        .header(header::COOKIE, "sid=a; ids=1,x")
+        .body(Body::empty())
+        .unwrap();
+    let resp = call(req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let body = rejection(resp).await;
+    assert_eq!(body["source"].as_str(), Some("cookie"));
+    assert_eq!(body["value"].as_str(), Some("x"));
+}
+
+#[tokio::test]
+async fn ignores_foreign_non_ascii_cookies() {
+    let req = request("/cookie")
+        .header(
+            header::COOKIE,
+            HeaderValue::from_bytes("other=caf\u{e9}; sid=abc".as_bytes()).unwrap(),
+        )
+        .body(Body::empty())
+        .unwrap();
+    let resp = call(req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(body_text(resp).await, "abc None []");
+
+    let req = request("/fallback")
+        .header(
+            header::COOKIE,
+            HeaderValue::from_bytes("other=caf\u{e9}; api_key=c".as_bytes()).unwrap(),
+        )
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(
+        body_text(call(req).await).await,
+        "cookie api_key c None None"
+    );
+
+    let req = request("/cookie")
+        .header(
+            header::COOKIE,
+            HeaderValue::from_bytes(b"sid=\xff").unwrap(),
+        )
+        .body(Body::empty())
+        .unwrap();
+    let resp = call(req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let body = rejection(resp).await;
+    assert_eq!(body["source"].as_str(), Some("cookie"));
+    assert_eq!(body["name"].as_str(), Some("sid"));
+    assert_eq!(body["field"].as_str(), Some("session"));
+}
+
+fn multipart_body(parts: &[(&str, Option<&str>, &str)]) -> Body {
+    let mut body = String::new();
+    for (name, file_name, value) in parts {
+        body.push_str("--BOUNDARY\r\n");
+        match file_name {
+            Some(file_name) => body.push_str(&format!(
+                "Content-Disposition: form-data; name=\"{name}\"; filename=\"{file_name}\"\r\n\
+                 Content-Type: text/plain\r\n"
+            )),
+            None => body.push_str(&format!(
+                "Content-Disposition: form-data; name=\"{name}\"\r\n"
+            )),
+        }
+        body.push_str(&format!("\r\n{value}\r\n"));
+    }
+    body.push_str("--BOUNDARY--\r\n");
+    Body::from(body)
+}
+
+fn upload_request(parts: &[(&str, Option<&str>, &str)]) -> Request {
+    request("/upload")
+        .header(
+            header::CONTENT_TYPE,
+            "multipart/form-data; boundary=BOUNDARY",
+        )
+        .body(multipart_body(parts))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn binds_multipart_text_and_files() {
+    let req = upload_request(&[
+        ("title", None, "hello"),
+        ("count", None, "3"),
+        ("avatar", Some("a.txt"), "abc"),
+        ("attachments", Some("1.txt"), "1"),
+        ("attachments", Some("2.txt"), "2"),
+    ]);
+    let resp = call(req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body = resp.into_body().into_string().await.unwrap();
+    assert_eq!(body, r#"hello 3 Some("a.txt") Some("text/plain") b"abc" 2"#);
+}
+
+#[tokio::test]
+async fn rejects_multipart_over_limits() {
+    let req = upload_request(&[
+        ("title", None, "hello"),
+        ("avatar", Some("a.txt"), "abc"),
+        ("note", Some("n.txt"), "more than eight bytes"),
+    ]);
+    let resp = call(req).await;
+    assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(rejection(resp).await["name"].as_str(), Some("note"));
+
+    let big = "x".repeat(65);
+    let req = upload_request(&[("title", None, &big), ("avatar", Some("a.txt"), "abc")]);
+    assert_eq!(call(req).await.status(), StatusCode::PAYLOAD_TOO_LARGE);
+}
+
+#[tokio::test]
+async fn rejects_missing_multipart_file() {
+    let resp = call(upload_request(&[("title", None, "hello")])).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let body = rejection(resp).await;
+    assert_eq!(body["code"].as_str(), Some("missing_field"));
+    assert_eq!(body["name"].as_str(), Some("avatar"));
+}
+
+async fn call_body(uri: &str, content_type: Option<&str>, payload: &'static str) -> Response {
+    let mut req = request(uri);
+    if let Some(content_type) = content_type {
+        req = req.header(header::CONTENT_TYPE, content_type);
+    }
+    call(req.body(Body::from(payload)).unwrap()).await
+}
+
+#[tokio::test]
+async fn negotiates_body_by_content_type() {
+    for (content_type, payload, expected) in [
+        ("application/json", r#"{"name":"volo"}"#, "volo 0"),
+        ("application/problem+json", r#"{"name":"volo"}"#, "volo 0"),
+        (
+            "application/json; charset=UTF-8",
+            r#"{"name":"volo"}"#,
+            "volo 0",
+        ),
+        (
+            "application/json; charset=utf8",
+            r#"{"name":"volo"}"#,
+            "volo 0",
+        ),
+        (
+            "application/json; charset=\"UTF-8\"",
+            r#"{"name":"volo"}"#,
+            "volo 0",
+        ),
+        ("application/x-www-form-urlencoded", "uid=7", " 7"),
+        (
+            "application/x-www-form-urlencoded; charset=utf-8",
+            "uid=7",
+            " 7",
+        ),
+        (
+            "application/x-www-form-urlencoded; charset=\"utf8\"",
+            "uid=7",
+            " 7",
+        ),
+    ] {
+        let resp = call_body("/body", Some(content_type), payload).await;
+        assert_eq!(
+            resp.status(),
+            StatusCode::OK,
+            "content type: {content_type}"
+        );
+        let body = resp.into_body().into_string().await.unwrap();
+        assert_eq!(body, expected, "content type: {content_type}");
+    }
+
+    let resp = call_body("/body", None, "").await;
+    assert_eq!(resp.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn rejects_unsupported_content_type() {
+    for content_type in [
+        Some("text/plain"),
+        Some("text/foo+json"),
+        Some("not a mime"),
+        None,
+    ] {
+        let resp = call_body("/body", content_type, "uid=7").await;
+        assert_eq!(
+            resp.status(),
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            "content type: {content_type:?}"
+        );
+        let body = rejection(resp).await;
+        assert_eq!(body["code"].as_str(), Some("unsupported_media_type"));
+        assert_eq!(body["value"].as_str(), content_type);
+    }
+
+    let content_type = "application/x-www-form-urlencoded; charset=ISO-8859-1";
+    let resp = call_body("/body", Some(content_type), "uid=7").await;
+    assert_eq!(resp.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    let body = rejection(resp).await;
+    assert_eq!(body["code"].as_str(), Some("unsupported_charset"));
+    assert_eq!(body["value"].as_str(), Some(content_type));
+    assert_eq!(
+        body["message"].as_str(),
+        Some(
+            "unsupported charset `iso-8859-1` in content type \
+             `application/x-www-form-urlencoded; charset=ISO-8859-1`, only utf-8 is accepted"
+        )
+    );
+}
+
+#[tokio::test]
+async fn restricts_accepted_content_types() {
+    let resp = call_body("/json-only", Some("application/json"), r#"{"name":"volo"}"#).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let resp = call_body(
+        "/json-only",
+        Some("application/x-www-form-urlencoded"),
+        "uid=7",
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    let message = rejection(resp).await["message"]
+        .as_str()
+        .unwrap()
+        .to_owned();
+    assert!(
+        message.ends_with("expected one of: application/json"),
+        "{message}"
+    );
+}
+
+async fn call_bytes(uri: &str, content_type: &str, payload: Vec<u8>) -> Response {
+    let req = request(uri)
+        .header(header::CONTENT_TYPE, content_type)
+        .body(Body::from(payload))
+        .unwrap();
+    call(req).await
+}
+
+#[derive(serde::Serialize)]
+struct CodecPayload {
+    name: &'static str,
+    uid: i64,
+}
+
+#[tokio::test]
+async fn decodes_msgpack_and_cbor_bodies() {
+    let payload = CodecPayload {
+        name: "volo",
+        uid: 7,
+    };
+
+    let msgpack = rmp_serde::to_vec_named(&payload).unwrap();
+    let resp = call_bytes("/codec", "application/x-msgpack", msgpack).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.into_body().into_string().await.unwrap(), "volo 0");
+
+    let mut cbor = Vec::new();
+    ciborium::into_writer(&payload, &mut cbor).unwrap();
+    let resp = call_bytes("/codec", "application/cbor", cbor).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.into_body().into_string().await.unwrap(), " 7");
+
+    let resp = call_bytes("/codec", "application/msgpack", vec![0xc1]).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(rejection(resp).await["source"].as_str(), Some("msgpack"));
+
+    let resp = call_bytes("/codec", "application/json", b"{}".to_vec()).await;
+    assert_eq!(resp.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+}
+
+#[tokio::test]
+async fn decodes_protobuf_body() {
+    let greeting = Greeting {
+        name: "volo".to_owned(),
+    };
+    let resp = call_bytes(
+        "/protobuf",
+        "application/x-protobuf",
+        prost::Message::encode_to_vec(&greeting),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.into_body().into_string().await.unwrap(), "volo");
+
+    let resp = call_bytes("/protobuf", "application/protobuf", vec![0xff]).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let body = rejection(resp).await;
+    assert_eq!(body["source"].as_str(), Some("protobuf"));
+    assert_eq!(body["field"].as_str(), Some("greeting"));
+
+    let resp = call(request("/protobuf").body(Body::empty()).unwrap()).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        rejection(resp).await["code"].as_str(),
+        Some("missing_field")
+    );
+}
+
+#[tokio::test]
+async fn decodes_custom_codec_body() {
+    let resp = call_bytes(
+        "/custom-codec",
+        "application/x-sonic",
+        br#"{"name":"volo"}"#.to_vec(),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.into_body().into_string().await.unwrap(), "volo");
+
+    let resp = call_bytes(
+        "/custom-codec",
+        "application/json",
+        br#"{"name":"volo"}"#.to_vec(),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    let message = rejection(resp).await["message"]
+        .as_str()
+        .unwrap()
+        .to_owned();
+    assert!(
+        message.ends_with("expected one of: application/x-sonic"),
+        "{message}"
+    );
+}
+
+#[tokio::test]
+async fn decodes_xml_body() {
+    let payload = "<req><Name>volo</Name><uid>7</uid></req>";
+    for content_type in [
+        "application/xml",
+        "text/xml",
+        "application/atom+xml; charset=utf-8",
+    ] {
+        let resp = call_bytes("/xml?page=2", content_type, payload.into()).await;
+        assert_eq!(
+            resp.status(),
+            StatusCode::OK,
+            "content type: {content_type}"
+        );
+        assert_eq!(resp.into_body().into_string().await.unwrap(), "volo 7 2");
+    }
+
+    let resp = call_bytes(
+        "/xml",
+        "application/xml",
+        b"<req><uid>7</uid></req>".to_vec(),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(rejection(resp).await["source"].as_str(), Some("xml"));
+
+    let resp = call_bytes("/xml", "application/json", br#"{"Name":"volo"}"#.to_vec()).await;
+    assert_eq!(resp.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+}
+
+#[tokio::test]
+async fn binds_flattened_structs() {
+    let req = request("/flatten?page=2&size=10")
+        .header("x-token", "abc")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(r#"{"name":"volo"}"#))
+        .unwrap();
+    let resp = call(req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(
+        resp.into_body().into_string().await.unwrap(),
+        "2 10 abc None volo"
+    );
+
+    let req = request("/flatten")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(r#"{"name":"volo"}"#))
+        .unwrap();
+    let resp = call(req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let body = rejection(resp).await;
+    assert_eq!(body["code"].as_str(), Some("missing_field"));
+    assert_eq!(body["name"].as_str(), Some("x-token"));
+}
+
+#[tokio::test]
+async fn accepts_valid_values() {
+    let req = request("/validated/volo?email=a@b.io")
+        .header("x-page", "3")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(r#"{"name":"volo"}"#))
+        .unwrap();
+    let resp = call(req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(
+        resp.into_body().into_string().await.unwrap(),
+        r#"3 volo Some("a@b.io") volo 0"#
+    );
+}
+
+#[tokio::test]
+async fn collects_every_validation_failure() {
+    let req = request("/validated/x?email=nope")
+        .header("x-page", "0")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(r#"{"name":"Admin1"}"#))
+        .unwrap();
+    let resp = call(req).await;
+    assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body = rejection(resp).await;
+    assert_eq!(body["code"].as_str(), Some("validation_failed"));
+    let errors: Vec<_> = body["errors"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| {
+            (
+                e["source"].as_str().unwrap().to_owned(),
+                e["name"].as_str().unwrap().to_owned(),
+                e["message"].as_str().unwrap().to_owned(),
+            )
+        })
+        .collect();
+    let expected = [
+        ("header", "x-page", "must be between 1 and 100"),
+        ("uri", "slug", "length must be between 2 and 8"),
+        ("query", "email", "must be a valid email address"),
+        ("json", "name", "must match `^[a-z]*$`"),
+    ];
+    assert_eq!(errors.len(), expected.len(), "{errors:?}");
+    for ((source, name, message), expected) in errors.iter().zip(expected) {
+        assert_eq!((source.as_str(), name.as_str(), message.as_str()), expected);
+    }
+
+    let req = request("/validated/volo")
+        .header("x-page", "1")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(r#"{"name":"admin"}"#))
+        .unwrap();
+    let body = rejection(call(req).await).await;
+    assert_eq!(body["errors"][0]["message"].as_str(), Some("is reserved"));
+
+    let req = request("/validated/volo")
+        .header("x-page", "1")
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(Body::from("uid=11"))
+        .unwrap();
+    let body = rejection(call(req).await).await;
+    assert_eq!(body["errors"][0]["source"].as_str(), Some("form"));
+    assert_eq!(
+        body["errors"][0]["message"].as_str(),
+        Some("must be at most 10")
+    );
+}
+
+#[tokio::test]
+async fn binds_generic_structs() {
+    let req = request("/paged?page=3")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(r#"{"body":{"name":"volo"}}"#))
+        .unwrap();
+    let resp = call(req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.into_body().into_string().await.unwrap(), "volo 3");
+
+    let req = request("/typed-headers")
+        .header("ids", "1,2")
+        .header("x-limit", "9")
+        .body(Body::empty())
+        .unwrap();
+    let resp = call(req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(
+        resp.into_body().into_string().await.unwrap(),
+        "[1, 2] Some(9) []"
+    );
+
+    let req = request("/typed-headers")
+        .header("ids", "1,300")
+        .body(Body::empty())
+        .unwrap();
+    let resp = call(req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(rejection(resp).await["value"].as_str(), Some("300"));
+
+    let req = request("/borrowed")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(r#"{"name":"volo"}"#))
+        .unwrap();
+    let resp = call(req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.into_body().into_string().await.unwrap(), "volo");
+}
+
+async fn body_text(resp: Response) -> String {
+    resp.into_body().into_string().await.unwrap()
+}
+
+#[tokio::test]
+async fn dispatches_enum_by_header_tag() {
+    let req = request("/versioned")
+        .header("x-api-version", "1")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(r#"{"name":"volo"}"#))
+        .unwrap();
+    assert_eq!(body_text(call(req).await).await, "v1 volo");
+
+    let req = request("/versioned?page=2")
+        .header("x-api-version", "2")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(r#"{"full_name":"volo http"}"#))
+        .unwrap();
+    assert_eq!(body_text(call(req).await).await, "v2 volo http 2");
+
+    for version in [Some("3"), None] {
+        let mut req = request("/versioned");
+        if let Some(version) = version {
+            req = req.header("x-api-version", version);
+        }
+        let resp = call(req.body(Body::empty()).unwrap()).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body = rejection(resp).await;
+        assert_eq!(body["source"].as_str(), Some("header"));
+        assert_eq!(body["name"].as_str(), Some("x-api-version"));
+        assert_eq!(body["value"].as_str(), version);
+        assert_eq!(body["message"].as_str(), Some("expected one of: 1, 2"));
+    }
+}
+
+#[tokio::test]
+async fn dispatches_enum_by_path_and_body_tag() {
+    let req = request("/path-versioned/v1")
+        .header("x-token", "abc")
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(body_text(call(req).await).await, "v1 abc");
+
+    let req = request("/path-versioned/ping").body(Body::empty()).unwrap();
+    assert_eq!(body_text(call(req).await).await, "ping");
+
+    let req = request("/path-versioned/v1").body(Body::empty()).unwrap();
+    let resp = call(req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(rejection(resp).await["name"].as_str(), Some("x-token"));
+
+    for (payload, expected) in [
+        (r#"{"type":"circle","radius":1.5}"#, "circle 1.5"),
+        (r#"{"type":"rect","w":2,"h":3}"#, "rect 2x3"),
+    ] {
+        let req = request("/shape")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(payload))
+            .unwrap();
+        assert_eq!(body_text(call(req).await).await, expected);
+    }
+
+    let req = request("/shape")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(r#"{"type":"square"}"#))
+        .unwrap();
+    let resp = call(req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(rejection(resp).await["source"].as_str(), Some("json"));
+}
+
+#[tokio::test]
+async fn validates_enum_variant_fields() {
+    let req = request("/shape")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(r#"{"type":"rect","w":0,"h":300}"#))
+        .unwrap();
+    let resp = call(req).await;
+    assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body = rejection(resp).await;
+    let fields: Vec<_> = body["errors"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|error| error["field"].as_str().unwrap())
+        .collect();
+    assert_eq!(fields, ["w", "h"]);
+}
+
+#[tokio::test]
+async fn renames_fields_per_source() {
+    let req = request("/renamed?pageSize=20&sort=name&q=volo")
+        .header("request-id", "r1")
+        .header("x-trace", "t1")
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(
+        body_text(call(req).await).await,
+        r#"r1 Some("t1") 20 Some("name") Some("volo")"#
+    );
+
+    let req = request("/renamed?page_size=20")
+        .header("request-id", "r1")
+        .body(Body::empty())
+        .unwrap();
+    let resp = call(req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(rejection(resp).await["source"].as_str(), Some("query"));
+
+    let req = request("/renamed?pageSize=20").body(Body::empty()).unwrap();
+    let resp = call(req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(rejection(resp).await["name"].as_str(), Some("request-id"));
+}
+
+#[tokio::test]
+async fn binds_repeated_query_keys_and_headers() {
+    let req = request("/multi-value?tags=a&tags=b&ids=1,2&ids=3&sort[]=name&sort[]=-id&page=2")
+        .header("x-role", "admin")
+        .header("x-role", "dev,ops")
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(
+        body_text(call(req).await).await,
+        r#"["a", "b"] [1, 2, 3] Some(["name", "-id"]) Some(2) ["admin", "dev", "ops"]"#
+    );
+
+    let req = request("/multi-value?tags=a&sort=name")
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(
+        body_text(call(req).await).await,
+        r#"["a"] [] Some(["name"]) None []"#
+    );
+
+    let req = request("/multi-value?page=1&page=2")
+        .body(Body::empty())
+        .unwrap();
+    let resp = call(req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(rejection(resp).await["source"].as_str(), Some("query"));
+
+    let req = request("/multi-value?ids=1,x").body(Body::empty()).unwrap();
+    let resp = call(req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(rejection(resp).await["source"].as_str(), Some("query"));
+}
+
+#[tokio::test]
+async fn ignores_repeated_unbound_query_keys() {
+    let req = request("/multi-value?page=1&utm=a&utm=b")
+        .body(Body::empty())
+        .unwrap();
+    let resp = call(req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(body_text(resp).await, r#"[] [] None Some(1) []"#);
+}
+
+#[tokio::test]
+async fn splits_lists_by_delimiter_into_collections() {
+    let req = request("/list/1|2|3")
+        .header("ids", " 1; 2;;3 ")
+        .header("x-tags", "b, a,,b")
+        .header("x-sorted", "3,1,2")
+        .header("x-small", "7, 8")
+        .header("x-pair", "-1,1")
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(
+        body_text(call(req).await).await,
+        r#"[1, 2, 3] ["a", "b"] {1, 2, 3} [7, 8] Some([-1, 1]) [1, 2, 3]"#
+    );
+
+    let req = request("/list/1|2")
+        .header("ids", "")
+        .header("x-tags", "a")
+        .body(Body::empty())
+        .unwrap();
+    let resp = call(req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let body = rejection(resp).await;
+    assert_eq!(body["source"].as_str(), Some("uri"));
+    assert_eq!(body["name"].as_str(), Some("rgb"));
+    assert_eq!(body["message"].as_str(), Some("expected 3 values, found 2"));
+
+    let req = request("/list/1|2|3")
+        .header("ids", "1;x")
+        .header("x-tags", "a")
+        .body(Body::empty())
+        .unwrap();
+    let resp = call(req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(rejection(resp).await["value"].as_str(), Some("x"));
+}
+
+fn convert_request(uri: &str, version: &str, page: &str, cursor: &str) -> Request {
+    request(uri)
+        .header("x-api-version", version)
+        .header("x-page", page)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(format!(r#"{{"cursor":"{cursor}"}}"#)))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn converts_fields_with_parse_fn_and_try_from() {
+    let req = convert_request("/convert/v1?after=7.sig", "v2", "3", "9.sig");
+    assert_eq!(
+        body_text(call(req).await).await,
+        "ApiVersion(2) ApiVersion(1) Some(Cursor(7)) Cursor(9) PageNo(3)"
+    );
+
+    let req = convert_request("/convert/v1", "2", "3", "9.sig");
+    let resp = call(req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let body = rejection(resp).await;
+    assert_eq!(body["name"].as_str(), Some("x-api-version"));
+    assert_eq!(
+        body["message"].as_str(),
+        Some("`2` is not a version like v2")
+    );
+
+    for (uri, page, cursor, name, message) in [
+        (
+            "/convert/v1",
+            "3",
+            "9.bad",
+            "cursor",
+            "cursor signature mismatch",
+        ),
+        (
+            "/convert/v1?after=1",
+            "3",
+            "9.sig",
+            "after",
+            "cursor signature mismatch",
+        ),
+        ("/convert/v1", "0", "9.sig", "x-page", "pages start at 1"),
+    ] {
+        let resp = call(convert_request(uri, "v2", page, cursor)).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body = rejection(resp).await;
+        assert_eq!(body["name"].as_str(), Some(name));
+        assert_eq!(body["message"].as_str(), Some(message));
+    }
+}
+
+#[tokio::test]
+async fn reads_fallback_chains_in_order() {
+    for (uri, api_key, cookie, expected) in [
+        (
+            "/fallback?api_key=q",
+            Some("h"),
+            None,
+            "header x-api-key h None None",
+        ),
+        (
+            "/fallback?api_key=q&tags=a",
+            None,
+            None,
+            r#"query api_key q None Some(["a"])"#,
+        ),
+        (
+            "/fallback?api_key=q&tags=a&tags=b,c",
+            None,
+            None,
+            r#"query api_key q None Some(["a", "b", "c"])"#,
+        ),
+        (
+            "/fallback",
+            None,
+            Some("api_key=c"),
+            "cookie api_key c None None",
+        ),
+        (
+            "/fallback/2?page=2",
+            Some("h"),
+            None,
+            "header x-api-key h Some(2) None",
+        ),
+        (
+            "/fallback?page=3",
+            Some("h"),
+            None,
+            "header x-api-key h Some(3) None",
+        ),
+    ] {
+        let mut req = request(uri);
+        if let Some(api_key) = api_key {
+            req = req.header("x-api-key", api_key);
+        }
+        if let Some(cookie) = cookie {
+            req = req.header(header::COOKIE, cookie);
+        }
+        let resp = call(req.body(Body::empty()).unwrap()).await;
+        assert_eq!(body_text(resp).await, expected, "{uri}");
+    }
+
+    let req = request("/fallback?tags=q")
+        .header("x-api-key", "h")
+        .header("x-tags", "a, b")
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(
+        body_text(call(req).await).await,
+        r#"header x-api-key h None Some(["a", "b"])"#
+    );
+
+    let req = request("/fallback").body(Body::empty()).unwrap();
+    let resp = call(req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let body = rejection(resp).await;
+    assert_eq!(body["code"].as_str(), Some("missing_field"));
+    assert_eq!(body["name"].as_str(), Some("x-api-key"));
+
+    let req = request("/fallback/2?page=3")
+        .header("x-api-key", "h")
+        .body(Body::empty())
+        .unwrap();
+    let resp = call(req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let body = rejection(resp).await;
+    assert_eq!(body["code"].as_str(), Some("conflicting_field"));
+    assert_eq!(body["field"].as_str(), Some("page"));
+    assert_eq!(
+        body["message"].as_str(),
+        Some("conflicting values for `page` in uri `page` and query `page`")
+    );
+}
+
+#[tokio::test]
+async fn validates_sourced_fields_on_their_value() {
+    let req = request("/fallback?api_key=much-too-long")
+        .body(Body::empty())
+        .unwrap();
+    let resp = call(req).await;
+    assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body = rejection(resp).await;
+    assert_eq!(body["errors"][0]["field"].as_str(), Some("api_key"));
+}
+
+#[tokio::test]
+async fn keeps_field_names_apart_from_generated_locals() {
+    let req = request("/shadow?page=3&page_from=query")
+        .header("x-json-mode", "strict")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(r#"{"name":"volo"}"#))
+        .unwrap();
+    assert_eq!(body_text(call(req).await).await, "strict 3 query volo");
+}
+
+#[tokio::test]
+async fn binds_raw_identifier_fields() {
+    let req = request("/raw?loop=2&match=3")
+        .header("type", "t")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(r#"{"ref":"r"}"#))
+        .unwrap();
+    assert_eq!(body_text(call(req).await).await, "t Some(2) 3 r");
+
+    let req = request("/raw?match=11")
+        .header("type", "t")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(r#"{"ref":"r"}"#))
+        .unwrap();
+    let resp = call(req).await;
+    assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body = rejection(resp).await;
+    assert_eq!(body["errors"][0]["name"].as_str(), Some("match"));
+    assert_eq!(body["errors"][0]["field"].as_str(), Some("match"));
+}
+
+#[tokio::test]
+async fn round_trips_client_requests() {
+    let param = ClientParam {
+        pid: 7,
+        cids: vec![1, 2],
+        token: Some("abc".to_owned()),
+        ids: vec![3, 4],
+        tags: vec!["a".to_owned(), "b c".to_owned()],
+        page: None,
+        session: "s-1".to_owned(),
+        api_key: Sourced {
+            value: "k".to_owned(),
+            source: mapping::Source::Header,
+            name: "x-api-key",
+        },
+        paging: ClientPaging {
+            offset: 20,
+            trace: None,
+        },
+        user_id: None,
+        title: "volo".to_owned(),
+        labels: vec!["http".to_owned()],
+    };
+    let req = param
+        .clone()
+        .into_request(Method::POST, ClientParam::PATH)
+        .unwrap();
+    assert_eq!(ClientParam::PATH, "/client/{pid}/{cids}");
+    assert_eq!(req.uri().path(), "/client/7/1,2");
+    assert_eq!(body_text(call(req).await).await, format!("{param:?}"));
+
+    let form = ClientForm {
+        tags: vec!["x".to_owned()],
+        name: "volo http".to_owned(),
+        age: Some(3),
+    };
+    let req = form
+        .clone()
+        .into_request(Method::POST, "/client-form")
+        .unwrap();
+    assert_eq!(req.uri().query(), Some("tags%5B%5D=x"));
+    assert_eq!(body_text(call(req).await).await, format!("{form:?}"));
+}
+
+#[tokio::test]
+async fn rejects_client_requests_that_do_not_fit_the_route() {
+    let form = ClientForm {
+        tags: Vec::new(),
+        name: "volo".to_owned(),
+        age: None,
+    };
+    let err = form
+        .clone()
+        .into_request(Method::POST, "/client-form/{id}")
+        .unwrap_err();
+    assert_eq!(err.to_string(), "no value for path placeholder `id`");
+
+    let param = ClientPaging {
+        offset: 0,
+        trace: Some("bad\nvalue".to_owned()),
+    };
+    let err = param.into_request(Method::GET, "/paged").unwrap_err();
+    assert!(
+        err.to_string().starts_with("invalid header `x-trace`"),
+        "{err}"
+    );
+}
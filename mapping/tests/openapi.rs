@@ -0,0 +1,238 @@
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+use mapping::{
+    ApiMethod, ApiRouter, Mapping, OpenApi, OpenApiSchema, Sourced, openapi::ParameterIn,
+};
+use sonic_rs::{JsonContainerTrait, JsonValueTrait, Value};
+use volo::{Service, net::Address};
+use volo_http::{
+    body::{Body, BodyConversion},
+    context::ServerContext,
+    http::{Method, StatusCode},
+    request::Request,
+    response::Response,
+    server::{Router, route::any},
+};
+
+#[derive(Mapping, Debug)]
+pub struct Paging {
+    /// Items to skip.
+    #[query]
+    #[validate(range(min = 0, max = 1000))]
+    pub offset: Option<u32>,
+}
+
+/// Create an article.
+#[derive(Mapping, Debug)]
+#[mapping(header(rename_all = "kebab-case"))]
+pub struct CreateArticle {
+    /// Owning user.
+    #[uri]
+    pub uid: u64,
+    #[header]
+    pub x_request_id: Option<String>,
+    #[query(style = "comma")]
+    pub tags: Vec<String>,
+    #[cookie]
+    #[validate(length(min = 8))]
+    pub session: String,
+    #[mapping(from(header("x-api-key"), query("api_key")))]
+    pub api_key: Sourced<String>,
+    #[mapping(flatten)]
+    pub paging: Paging,
+    #[ext]
+    pub user_id: Option<i64>,
+    /// Article title.
+    #[json]
+    #[validate(length(min = 1, max = 64))]
+    pub title: String,
+    #[json]
+    #[validate(email)]
+    pub contact: Option<String>,
+    #[json]
+    #[serde(default)]
+    pub scores: Vec<f64>,
+    #[json]
+    #[serde(rename = "default_topic")]
+    pub topic: String,
+}
+
+#[derive(Mapping, Debug)]
+#[mapping(tag = header("x-api-version"))]
+pub enum Versioned {
+    #[mapping(rename = "v1")]
+    V1 {
+        #[json]
+        name: String,
+    },
+    #[mapping(rename = "v2")]
+    V2 {
+        #[json]
+        name: String,
+        #[query]
+        page: u32,
+    },
+}
+
+fn parameter<'a>(operation: &'a Value, name: &str) -> &'a Value {
+    operation["parameters"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|parameter| parameter["name"].as_str() == Some(name))
+        .unwrap_or_else(|| panic!("no parameter `{name}`"))
+}
+
+#[test]
+fn describes_parameters_per_source() {
+    let parameters = CreateArticle::openapi_parameters();
+    let names: Vec<_> = parameters
+        .iter()
+        .map(|parameter| (parameter.name.as_str(), parameter.location))
+        .collect();
+    assert_eq!(
+        names,
+        [
+            ("uid", ParameterIn::Path),
+            ("x-request-id", ParameterIn::Header),
+            ("tags", ParameterIn::Query),
+            ("session", ParameterIn::Cookie),
+            ("x-api-key", ParameterIn::Header),
+            ("api_key", ParameterIn::Query),
+            ("offset", ParameterIn::Query),
+        ]
+    );
+    assert_eq!(
+        CreateArticle::openapi_description(),
+        Some("Create an article.")
+    );
+}
+
+async fn create(_: CreateArticle) {}
+
+async fn rename(_: Versioned) {}
+
+async fn list(_: Paging) -> &'static str {
+    "listed"
+}
+
+fn api() -> ApiRouter {
+    ApiRouter::new(OpenApi::new("articles", "1.0"))
+        .route::<CreateArticle>("/users/{uid}/articles", ApiMethod::Post, any(create))
+        .route::<Paging>("/users/{uid}/articles", ApiMethod::Get, any(list))
+        .route::<Versioned>("/names/{*rest}", ApiMethod::Put, any(rename))
+}
+
+async fn call(router: &Router, req: Request) -> Response {
+    let addr = Address::from(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 8000));
+    let mut cx = ServerContext::new(addr);
+    router.call(&mut cx, req).await.unwrap()
+}
+
+#[test]
+fn writes_an_openapi_document() {
+    let doc = sonic_rs::to_string(api().doc()).unwrap();
+    let doc: Value = sonic_rs::from_str(&doc).unwrap();
+    assert_eq!(doc["openapi"].as_str(), Some("3.1.0"));
+
+    let operation = &doc["paths"]["/users/{uid}/articles"]["post"];
+    assert_eq!(
+        operation["description"].as_str(),
+        Some("Create an article.")
+    );
+    let uid = parameter(operation, "uid");
+    assert_eq!(uid["in"].as_str(), Some("path"));
+    assert_eq!(uid["required"].as_bool(), Some(true));
+    assert_eq!(uid["description"].as_str(), Some("Owning user."));
+    assert_eq!(uid["schema"]["type"].as_str(), Some("integer"));
+    assert_eq!(uid["schema"]["format"].as_str(), Some("int64"));
+    assert_eq!(
+        parameter(operation, "x-request-id")["required"].as_bool(),
+        Some(false)
+    );
+    let tags = parameter(operation, "tags");
+    assert_eq!(tags["explode"].as_bool(), Some(false));
+    assert_eq!(tags["schema"]["items"]["type"].as_str(), Some("string"));
+    assert_eq!(
+        parameter(operation, "session")["schema"]["minLength"].as_u64(),
+        Some(8)
+    );
+    assert_eq!(
+        parameter(operation, "api_key")["required"].as_bool(),
+        Some(false)
+    );
+    let offset = parameter(operation, "offset");
+    assert_eq!(offset["required"].as_bool(), Some(false));
+    assert_eq!(offset["schema"]["maximum"].as_u64(), Some(1000));
+
+    let body = &operation["requestBody"];
+    assert_eq!(body["required"].as_bool(), Some(true));
+    let schema = &body["content"]["application/json"]["schema"];
+    assert_eq!(schema["type"].as_str(), Some("object"));
+    let required: Vec<_> = schema["required"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|name| name.as_str().unwrap())
+        .collect();
+    assert_eq!(required, ["title", "default_topic"]);
+    let title = &schema["properties"]["title"];
+    assert_eq!(title["description"].as_str(), Some("Article title."));
+    assert_eq!(title["maxLength"].as_u64(), Some(64));
+    assert_eq!(
+        schema["properties"]["contact"]["format"].as_str(),
+        Some("email")
+    );
+    assert_eq!(
+        schema["properties"]["scores"]["items"]["format"].as_str(),
+        Some("double")
+    );
+    assert_eq!(operation["parameters"].as_array().unwrap().len(), 7);
+
+    let operation = &doc["paths"]["/names/{rest}"]["put"];
+    let tag = parameter(operation, "x-api-version");
+    assert_eq!(tag["required"].as_bool(), Some(true));
+    let tags: Vec<_> = tag["schema"]["enum"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|tag| tag.as_str().unwrap())
+        .collect();
+    assert_eq!(tags, ["v1", "v2"]);
+    assert_eq!(
+        parameter(operation, "page")["required"].as_bool(),
+        Some(false)
+    );
+}
+
+#[tokio::test]
+async fn serves_the_routes_and_their_document() {
+    let router = api().into_router("/openapi.json");
+    let req = Request::builder()
+        .uri("/openapi.json")
+        .body(Body::empty())
+        .unwrap();
+    let resp = call(&router, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = sonic_rs::from_str(&resp.into_body().into_string().await.unwrap()).unwrap();
+    assert_eq!(body["info"]["title"].as_str(), Some("articles"));
+    let path = &body["paths"]["/users/{uid}/articles"];
+    assert!(path["post"].is_object());
+    assert!(path["get"].is_object());
+
+    let req = Request::builder()
+        .uri("/users/1/articles?offset=5")
+        .body(Body::empty())
+        .unwrap();
+    let resp = call(&router, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.into_body().into_string().await.unwrap(), "listed");
+
+    let req = Request::builder()
+        .method(Method::DELETE)
+        .uri("/users/1/articles")
+        .body(Body::empty())
+        .unwrap();
+    let resp = call(&router, req).await;
+    assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
+}
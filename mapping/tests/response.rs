@@ -0,0 +1,107 @@
+use mapping::IntoResponseMapping;
+use volo_http::{
+    body::BodyConversion,
+    http::{StatusCode, header},
+    response::Response,
+    server::IntoResponse,
+};
+
+#[derive(IntoResponseMapping)]
+#[mapping(header(rename_all = "kebab-case"))]
+pub struct Created {
+    #[status]
+    pub status: StatusCode,
+    #[header]
+    pub x_request_id: String,
+    #[header("x-tags")]
+    pub tags: Vec<String>,
+    #[header]
+    pub x_trace: Option<String>,
+    #[cookie]
+    pub session: String,
+    #[json]
+    pub id: u64,
+    #[json]
+    #[serde(rename = "displayName")]
+    pub name: String,
+}
+
+#[derive(IntoResponseMapping)]
+#[format("form")]
+pub struct Token {
+    #[status]
+    pub status: Option<StatusCode>,
+    pub access_token: String,
+    pub expires_in: u32,
+}
+
+#[derive(IntoResponseMapping)]
+pub struct Empty {
+    #[header("x-request-id")]
+    pub request_id: String,
+}
+
+async fn body_text(resp: Response) -> String {
+    resp.into_body().into_string().await.unwrap()
+}
+
+#[tokio::test]
+async fn writes_status_headers_cookies_and_body() {
+    let resp = Created {
+        status: StatusCode::CREATED,
+        x_request_id: "r-1".to_owned(),
+        tags: vec!["a".to_owned(), "b".to_owned()],
+        x_trace: None,
+        session: "s-1".to_owned(),
+        id: 7,
+        name: "volo".to_owned(),
+    }
+    .into_response();
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let headers = resp.headers();
+    assert_eq!(headers["x-request-id"], "r-1");
+    let tags: Vec<_> = headers.get_all("x-tags").iter().collect();
+    assert_eq!(tags, ["a", "b"]);
+    assert!(!headers.contains_key("x-trace"));
+    assert_eq!(headers[header::SET_COOKIE], "session=s-1");
+    assert_eq!(headers[header::CONTENT_TYPE], "application/json");
+    assert_eq!(body_text(resp).await, r#"{"id":7,"displayName":"volo"}"#);
+}
+
+#[tokio::test]
+async fn defaults_to_ok_and_encodes_form_bodies() {
+    let resp = Token {
+        status: None,
+        access_token: "t 1".to_owned(),
+        expires_in: 60,
+    }
+    .into_response();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(
+        resp.headers()[header::CONTENT_TYPE],
+        "application/x-www-form-urlencoded"
+    );
+    assert_eq!(body_text(resp).await, "access_token=t+1&expires_in=60");
+
+    let resp = Empty {
+        request_id: "r-2".to_owned(),
+    }
+    .into_response();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert!(!resp.headers().contains_key(header::CONTENT_TYPE));
+    assert_eq!(body_text(resp).await, "");
+}
+
+#[tokio::test]
+async fn turns_unwritable_fields_into_server_errors() {
+    let resp = Empty {
+        request_id: "bad\nvalue".to_owned(),
+    }
+    .into_response();
+    assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert!(
+        body_text(resp)
+            .await
+            .starts_with("invalid response header `x-request-id`")
+    );
+}
@@ -0,0 +1,9 @@
+use mapping::Mapping;
+
+#[derive(Mapping)]
+pub struct Param {
+    #[body]
+    pub id: u32,
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use mapping::Mapping;
+
+#[derive(Mapping)]
+#[mapping(client)]
+pub struct Param {
+    #[query]
+    pub page: u32,
+    #[msgpack]
+    pub name: String,
+}
+
+fn main() {}
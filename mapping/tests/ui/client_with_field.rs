@@ -0,0 +1,14 @@
+use mapping::Mapping;
+
+fn parse_hex(s: &str) -> Result<u32, std::num::ParseIntError> {
+    u32::from_str_radix(s, 16)
+}
+
+#[derive(Mapping)]
+#[mapping(client)]
+pub struct HexParam {
+    #[header(with = parse_hex)]
+    pub id: u32,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use mapping::Mapping;
+
+#[derive(Mapping)]
+pub struct Param {
+    #[header("x-page", default = "7")]
+    pub page: Option<u32>,
+}
+
+fn main() {}
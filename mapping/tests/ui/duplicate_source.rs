@@ -0,0 +1,10 @@
+use mapping::Mapping;
+
+#[derive(Mapping)]
+pub struct Param {
+    #[query]
+    #[header]
+    pub page: u32,
+}
+
+fn main() {}
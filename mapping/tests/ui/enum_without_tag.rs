@@ -0,0 +1,12 @@
+use mapping::Mapping;
+
+#[derive(Mapping)]
+pub enum Param {
+    V1 {
+        #[query]
+        id: u32,
+    },
+    V2,
+}
+
+fn main() {}
@@ -0,0 +1,16 @@
+use mapping::Mapping;
+
+#[derive(Mapping)]
+pub struct Pagination {
+    #[query]
+    pub page: u32,
+}
+
+#[derive(Mapping)]
+pub struct Param {
+    #[mapping(flatten)]
+    #[query]
+    pub pagination: Pagination,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use mapping::Mapping;
+
+#[derive(Mapping)]
+pub struct Param {
+    #[query]
+    #[validate(regex = "[a-z")]
+    pub name: String,
+}
+
+fn main() {}
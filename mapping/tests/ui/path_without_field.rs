@@ -0,0 +1,12 @@
+use mapping::Mapping;
+
+#[derive(Mapping)]
+#[mapping(path = "/test/{pid}/{cid}")]
+pub struct Param {
+    #[uri]
+    pub pid: i64,
+    #[query]
+    pub cid: String,
+}
+
+fn main() {}
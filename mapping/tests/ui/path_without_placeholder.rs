@@ -0,0 +1,12 @@
+use mapping::Mapping;
+
+#[derive(Mapping)]
+#[mapping(path = "/test/{pid}")]
+pub struct Param {
+    #[uri]
+    pub pid: i64,
+    #[uri]
+    pub cid: String,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use mapping::IntoResponseMapping;
+
+#[derive(IntoResponseMapping)]
+pub struct Reply {
+    #[query]
+    pub page: u32,
+}
+
+fn main() {}
@@ -0,0 +1,6 @@
+use mapping::Mapping;
+
+#[derive(Mapping)]
+pub struct Param(#[query] pub u32);
+
+fn main() {}
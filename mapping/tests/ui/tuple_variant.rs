@@ -0,0 +1,9 @@
+use mapping::Mapping;
+
+#[derive(Mapping)]
+#[mapping(tag = header("x-api-version"))]
+pub enum Param {
+    V1(u32),
+}
+
+fn main() {}
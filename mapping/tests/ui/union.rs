@@ -0,0 +1,9 @@
+use mapping::Mapping;
+
+#[derive(Mapping)]
+pub union Param {
+    pub id: u32,
+    pub count: u64,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use mapping::Mapping;
+
+#[derive(Mapping)]
+#[mapping(accept = ["json", "yaml"])]
+pub struct Param {
+    pub id: u32,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use mapping::Mapping;
+
+#[derive(Mapping)]
+#[format("yaml")]
+pub struct Param {
+    pub id: u32,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use mapping::Mapping;
+
+#[derive(Mapping)]
+pub struct Param {
+    #[mapping(from(header("x-api-key"), json))]
+    pub api_key: String,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use mapping::Mapping;
+
+#[derive(Mapping)]
+#[mapping(lenien)]
+pub struct Param {
+    #[query]
+    pub page: u32,
+}
+
+fn main() {}
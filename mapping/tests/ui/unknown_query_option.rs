@@ -0,0 +1,9 @@
+use mapping::Mapping;
+
+#[derive(Mapping)]
+pub struct Param {
+    #[query(fallback = "1")]
+    pub page: u32,
+}
+
+fn main() {}
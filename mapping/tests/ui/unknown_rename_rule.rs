@@ -0,0 +1,10 @@
+use mapping::Mapping;
+
+#[derive(Mapping)]
+#[mapping(header(rename_all = "kebab_case"))]
+pub struct Param {
+    #[header]
+    pub request_id: String,
+}
+
+fn main() {}
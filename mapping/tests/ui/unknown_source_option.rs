@@ -0,0 +1,9 @@
+use mapping::Mapping;
+
+#[derive(Mapping)]
+pub struct Param {
+    #[header(renme = "x-token")]
+    pub token: String,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use mapping::Mapping;
+
+#[derive(Mapping)]
+pub struct Param {
+    #[query]
+    #[validate(lenght(max = 8))]
+    pub name: String,
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+use mapping::Mapping;
+
+fn parse_page(s: &str) -> Result<u32, std::num::ParseIntError> {
+    s.parse()
+}
+
+#[derive(Mapping)]
+pub struct Param {
+    #[query(with = "parse_page")]
+    pub page: u32,
+}
+
+fn main() {}
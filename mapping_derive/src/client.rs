@@ -0,0 +1,244 @@
+use super::deserialize::{generic_marker, local_ident};
+use super::field_attr::*;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{Error, Generics, Ident, Token, WherePredicate, parse_quote, punctuated::Punctuated};
+
+// 客户端可以写入的请求体格式
+const CLIENT_BODY_FORMATS: &[&str] = &["json", "form"];
+
+/// `#[mapping(client)]`：按字段来源把结构体写回请求，生成 `IntoRequest`
+pub fn into_request_expanded(
+    struct_name: &Ident,
+    generics: &Generics,
+    field_infos: &[FieldInfo],
+) -> Result<TokenStream, Error> {
+    let mut body_format = None;
+    for field in field_infos {
+        // 自定义解析函数没有对应的写入方式，按 `Display` 写入无法被服务端原样读回
+        if field.parse_with.is_some() {
+            return Err(Error::new_spanned(
+                &field.f_type,
+                format!(
+                    "#[mapping(client)] cannot write `{}`, whose value is read with a custom `with` parser",
+                    field.name
+                ),
+            ));
+        }
+        let format = field.source_format();
+        match format {
+            "header" | "uri" | "query" | "cookie" | "ext" | "flatten" => {}
+            _ if CLIENT_BODY_FORMATS.contains(&format) => match body_format {
+                Some(body_format) if body_format != format => {
+                    return Err(Error::new_spanned(
+                        struct_name,
+                        "#[mapping(client)] structs can only write one body format",
+                    ));
+                }
+                _ => body_format = Some(format),
+            },
+            _ => {
+                return Err(Error::new_spanned(
+                    struct_name,
+                    format!("#[mapping(client)] does not support #[{format}] fields"),
+                ));
+            }
+        }
+    }
+
+    let generics = client_bounds(generics, field_infos);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let field_idents: Vec<_> = field_infos
+        .iter()
+        .map(|field| format_ident!("{}", field.name))
+        .collect();
+    let locals = field_infos.iter().map(|field| match field.format.as_str() {
+        "ext" => quote! { _ },
+        _ => {
+            let local = local_ident(field);
+            quote! { #local }
+        }
+    });
+    let write_expanded = field_infos.iter().map(write_expanded);
+    let error = quote! { ::mapping::client::RequestError };
+    let body_expanded =
+        body_format.map(|format| body_expanded(format, &generics, field_infos, &error));
+
+    Ok(quote! {
+        impl #impl_generics ::mapping::IntoRequest for #struct_name #ty_generics #where_clause {
+            fn write_to(
+                self,
+                writer: &mut ::mapping::client::RequestWriter,
+            ) -> Result<(), ::mapping::client::RequestError> {
+                let Self { #(#field_idents: #locals,)* } = self;
+                #(#write_expanded)*
+                #body_expanded
+                Ok(())
+            }
+        }
+    })
+}
+
+/// 写入单个非请求体字段，`from` 字段写入其第一个来源
+pub fn write_expanded(field: &FieldInfo) -> TokenStream {
+    let local = local_ident(field);
+    let format = field.source_format();
+    let name = field.from.first().map_or(&field.rename, |(_, name)| name);
+    let value = if field.is_sourced {
+        quote! { &__value.value }
+    } else {
+        quote! { __value }
+    };
+    let delimiter = field.delimiter.as_deref().unwrap_or(",");
+    let write = match format {
+        "flatten" => return quote! { ::mapping::IntoRequest::write_to(#local, writer)?; },
+        "header" if field.is_vec => quote! {
+            for __item in #value {
+                writer.header(#name, __item)?;
+            }
+        },
+        "header" => quote! { writer.header(#name, #value)?; },
+        "cookie" | "uri" if field.is_vec => {
+            let method = format_ident!("{}", if format == "uri" { "param" } else { "cookie" });
+            let result = (format == "cookie").then(|| quote!(?));
+            quote! {
+                writer.#method(#name, &::mapping::client::join(#value, #delimiter))#result;
+            }
+        }
+        "cookie" => quote! { writer.cookie(#name, #value)?; },
+        "uri" => quote! { writer.param(#name, #value); },
+        "query" => {
+            use heck::ToUpperCamelCase;
+            let style = format_ident!(
+                "{}",
+                field
+                    .style
+                    .as_deref()
+                    .unwrap_or("repeat")
+                    .to_upper_camel_case()
+            );
+            quote! { writer.query(#name, ::mapping::query::QueryStyle::#style, #value)?; }
+        }
+        // 请求体字段统一由 `body_expanded` 写入
+        _ => return quote! {},
+    };
+    if field.is_option && field.is_local() {
+        quote! {
+            if let Some(__value) = &#local {
+                #write
+            }
+        }
+    } else {
+        quote! {
+            let __value = &#local;
+            #write
+        }
+    }
+}
+
+/// 请求体字段移入 `XxxBody` 结构体，沿用字段上的 serde 属性编码，编码失败时转换为 `error`
+pub fn body_expanded(
+    format: &str,
+    generics: &Generics,
+    field_infos: &[FieldInfo],
+    error: &TokenStream,
+) -> TokenStream {
+    use heck::ToUpperCamelCase;
+    let body_name = format_ident!("{}Body", format.to_upper_camel_case());
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let turbofish = ty_generics.as_turbofish();
+    let body_fields: Vec<_> = field_infos
+        .iter()
+        .filter(|field| field.format == format)
+        .collect();
+    let mut field_definitions: Vec<_> = body_fields
+        .iter()
+        .map(|field| {
+            let ident = format_ident!("{}", field.name);
+            let serde = &field.serde;
+            let fty = &field.f_type;
+            // `try_from` 的 `Option<T>` 字段只记录了内层类型
+            let fty = if field.try_from.is_some() && field.is_option {
+                quote! { Option<#fty> }
+            } else {
+                quote! { #fty }
+            };
+            quote! {
+                #serde
+                #ident: #fty,
+            }
+        })
+        .collect();
+    let mut field_values: Vec<_> = body_fields
+        .iter()
+        .map(|field| {
+            let ident = format_ident!("{}", field.name);
+            let local = local_ident(field);
+            quote! { #ident: #local, }
+        })
+        .collect();
+    let serde_bound = match generic_marker(generics) {
+        Some(marker) => {
+            field_definitions.push(marker);
+            field_values.push(quote! { __marker: ::std::marker::PhantomData, });
+            quote! { #[serde(bound = "")] }
+        }
+        None => quote! {},
+    };
+    let encode = match format {
+        "json" => quote! {
+            writer.body(
+                "application/json",
+                sonic_rs::to_vec(&__body)
+                    .map_err(|e| #error::body(::mapping::Source::Json, e))?,
+            );
+        },
+        _ => quote! {
+            writer.body(
+                "application/x-www-form-urlencoded",
+                serde_urlencoded::to_string(&__body)
+                    .map_err(|e| #error::body(::mapping::Source::Form, e))?
+                    .into_bytes(),
+            );
+        },
+    };
+    quote! {
+        #[derive(serde::Serialize)]
+        #serde_bound
+        struct #body_name #impl_generics #where_clause {
+            #(#field_definitions)*
+        }
+        let __body = #body_name #turbofish {
+            #(#field_values)*
+        };
+        #encode
+    }
+}
+
+/// 字段类型用到泛型参数时，按字段来源补充写入请求或响应需要的约束
+pub fn client_bounds(generics: &Generics, field_infos: &[FieldInfo]) -> Generics {
+    let mut generics = generics.clone();
+    let type_params: Vec<_> = generics
+        .type_params()
+        .map(|param| param.ident.to_string())
+        .collect();
+    if type_params.is_empty() {
+        return generics;
+    }
+    let where_clause = generics.make_where_clause();
+    for field in field_infos {
+        let fty = &field.f_type;
+        if !super::mapping::uses_type_params(quote!(#fty), &type_params) {
+            continue;
+        }
+        let predicates = match field.source_format() {
+            "header" | "uri" | "cookie" => quote! { #fty: ::std::fmt::Display },
+            "flatten" => quote! { #fty: ::mapping::IntoRequest },
+            "ext" => continue,
+            _ => quote! { #fty: serde::Serialize },
+        };
+        let predicates: Punctuated<WherePredicate, Token![,]> = parse_quote!(#predicates);
+        where_clause.predicates.extend(predicates);
+    }
+    generics
+}
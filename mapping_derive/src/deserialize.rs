@@ -0,0 +1,763 @@
+use super::field_attr::*;
+use super::helper::Collection;
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote};
+use syn::{GenericParam, Generics};
+
+pub fn format_expanded(
+    format: &str,
+    field_formats: &Vec<FieldInfo>,
+    generics: &Generics,
+) -> TokenStream {
+    match format {
+        "json" => deserialize_expanded(
+            field_formats,
+            format,
+            generics,
+            |struct_name, struct_def_expanded, mode| {
+                quote! {
+                    // json deserialize
+                    #struct_def_expanded
+                    let #mode = match request_body.kind() {
+                        Some(::mapping::BodyKind::Json) => {
+                            sonic_rs::from_slice::<#struct_name>(request_body.bytes())
+                        }
+                        _ => sonic_rs::from_slice::<#struct_name>(b"{}"),
+                    }
+                    .map_err(|e| ::mapping::MappingRejection::decode(::mapping::Source::Json, e))?;
+                }
+            },
+        ),
+        "form" => deserialize_expanded(
+            field_formats,
+            format,
+            generics,
+            |struct_name, struct_def_expanded, mode| {
+                quote! {
+                    // form deserialize
+                    #struct_def_expanded
+                    let #mode = match request_body.kind() {
+                        Some(::mapping::BodyKind::Form) => {
+                            serde_urlencoded::from_bytes::<#struct_name>(request_body.bytes())
+                        }
+                        _ => serde_urlencoded::from_str::<#struct_name>(""),
+                    }
+                    .map_err(|e| ::mapping::MappingRejection::decode(::mapping::Source::Form, e))?;
+                }
+            },
+        ),
+        "query" => deserialize_expanded(
+            field_formats,
+            format,
+            generics,
+            |struct_name, struct_def_expanded, mode| {
+                use heck::ToUpperCamelCase;
+                let styles = field_formats.iter().filter_map(|field| {
+                    let rename = &field.rename;
+                    let style = format_ident!("{}", field.style.as_ref()?.to_upper_camel_case());
+                    Some(quote! { (#rename, ::mapping::query::QueryStyle::#style) })
+                });
+                quote! {
+                    // query deserialize
+                    #struct_def_expanded
+                    let #mode = ::mapping::query::decode::<#struct_name>(
+                        parts.uri.query().unwrap_or_default(),
+                        &[#(#styles),*],
+                    )?;
+                }
+            },
+        ),
+        "multipart" => deserialize_expanded(
+            field_formats,
+            format,
+            generics,
+            |struct_name, struct_def_expanded, mode| {
+                let file_arms = field_formats.iter().filter(|field| field.is_file).map(|field| {
+                let rename = &field.rename;
+                let local_ident = local_ident(field);
+                if field.is_vec {
+                    quote! {
+                        #rename => #local_ident.get_or_insert_with(Vec::new).push(part.file().await?),
+                    }
+                } else {
+                    quote! {
+                        #rename => #local_ident = Some(part.file().await?),
+                    }
+                }
+            });
+                quote! {
+                    // multipart deserialize
+                    #struct_def_expanded
+                    let mut texts: Vec<(String, String)> = Vec::new();
+                    if let Some(mut reader) = request_body.into_multipart() {
+                        while let Some(part) = reader.next_part().await? {
+                            let Some(name) = part.name().map(str::to_owned) else {
+                                continue;
+                            };
+                            match name.as_str() {
+                                #(#file_arms)*
+                                _ => texts.push((name, part.text().await?)),
+                            }
+                        }
+                    }
+                    let #mode = ::mapping::multipart::decode_text::<#struct_name>(&texts)?;
+                }
+            },
+        ),
+        "msgpack" | "cbor" | "xml" | "body" => deserialize_expanded(
+            field_formats,
+            format,
+            generics,
+            |struct_name, struct_def_expanded, mode| {
+                let codec = codec_path(format, field_formats);
+                let source = source_expanded(format);
+                quote! {
+                    // codec deserialize
+                    #struct_def_expanded
+                    let #mode = match request_body.kind() {
+                        Some(kind) if kind == <#codec as ::mapping::codec::BodyCodec>::KIND => {
+                            <#codec as ::mapping::codec::BodyCodec>::decode::<#struct_name>(
+                                request_body.bytes(),
+                            )
+                        }
+                        _ => ::mapping::codec::decode_empty::<#struct_name>(),
+                    }
+                    .map_err(|e| ::mapping::MappingRejection::decode(#source, e))?;
+                }
+            },
+        ),
+        "protobuf" => protobuf_deserialize_expanded(field_formats),
+        "header" => header_deserialize_expanded(field_formats),
+        "uri" => uri_deserialize_expanded(field_formats),
+        "cookie" => cookie_deserialize_expanded(field_formats),
+        "ext" => ext_deserialize_expanded(field_formats),
+        _ => quote! {},
+    }
+}
+
+fn deserialize_expanded<F>(
+    field_formats: &[FieldInfo],
+    format: &str,
+    generics: &Generics,
+    f: F,
+) -> TokenStream
+where
+    F: Fn(TokenStream, TokenStream, Ident) -> TokenStream,
+{
+    use heck::ToUpperCamelCase;
+    let mode_name = format_ident!("{}Mode", format.to_upper_camel_case());
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let turbofish = ty_generics.as_turbofish();
+    let struct_name = quote! { #mode_name #turbofish };
+    let mut field_definitions = Vec::new();
+    for field in field_formats.iter().filter(|field| !field.is_file) {
+        let field_name_ident = format_ident!("{}", field.name);
+        let field_type = decoded_type(field);
+        let mut serade_attr = quote! {};
+
+        if let Some(attr) = &field.serde {
+            serade_attr = quote! { #attr };
+        }
+
+        field_definitions.push(quote! {
+            #serade_attr
+            #field_name_ident: #field_type,
+        });
+    }
+    // 约束已由 `generic_bounds` 写入 where 子句，不再使用 serde 推断的约束
+    let serde_bound = match generic_marker(generics) {
+        Some(marker) => {
+            field_definitions.push(marker);
+            quote! { #[serde(bound = "")] }
+        }
+        None => quote! {},
+    };
+    let struct_def_expanded = quote! {
+        #[derive(serde::Deserialize)]
+        #serde_bound
+        struct #mode_name #impl_generics #where_clause {
+            #(#field_definitions)*
+        }
+    };
+    f(struct_name, struct_def_expanded, mode_ident(format))
+}
+
+/// 结构体内定义的类型不能直接使用外层的泛型参数，需要带上同样的泛型
+pub fn generic_marker(generics: &Generics) -> Option<TokenStream> {
+    let marker_types: Vec<_> = generics
+        .params
+        .iter()
+        .filter_map(|param| match param {
+            GenericParam::Type(ty) => {
+                let ident = &ty.ident;
+                Some(quote! { #ident })
+            }
+            GenericParam::Lifetime(lt) => {
+                let lifetime = &lt.lifetime;
+                Some(quote! { &#lifetime () })
+            }
+            GenericParam::Const(_) => None,
+        })
+        .collect();
+    (!marker_types.is_empty()).then(|| {
+        quote! {
+            #[serde(skip)]
+            __marker: ::std::marker::PhantomData<fn() -> (#(#marker_types,)*)>,
+        }
+    })
+}
+
+/// 经由 `XxxMode` 反序列化的类型，`try_from` 字段为其原始类型
+pub fn decoded_type(field: &FieldInfo) -> TokenStream {
+    match &field.try_from {
+        Some(raw) if field.is_option => quote! { Option<#raw> },
+        Some(raw) => raw.clone(),
+        None => field.f_type.clone(),
+    }
+}
+
+/// 请求体格式使用的解码器
+pub fn codec_path(format: &str, field_formats: &[FieldInfo]) -> TokenStream {
+    match format {
+        "msgpack" => quote! { ::mapping::codec::MsgPack },
+        "cbor" => quote! { ::mapping::codec::Cbor },
+        "xml" => quote! { ::mapping::codec::Xml },
+        _ => {
+            let codec = field_formats.iter().find_map(|field| field.codec.as_ref());
+            quote! { #codec }
+        }
+    }
+}
+
+/// 读取请求体：按 `Content-Type` 协商请求体格式，`accept` 为允许的格式
+pub fn body_expanded(
+    accept: &[String],
+    body_fields: Option<&Vec<FieldInfo>>,
+    multipart_fields: Option<&Vec<FieldInfo>>,
+    options: &MappingOptions,
+) -> TokenStream {
+    if accept.is_empty() {
+        return quote! {};
+    }
+    use heck::ToUpperCamelCase;
+    let accept = accept.iter().map(|format| match format.as_str() {
+        "msgpack" | "cbor" | "xml" | "body" => {
+            let codec = codec_path(format, body_fields.map(Vec::as_slice).unwrap_or_default());
+            quote! { <#codec as ::mapping::codec::BodyCodec>::KIND }
+        }
+        _ => {
+            let kind = format_ident!("{}", format.to_upper_camel_case());
+            quote! { ::mapping::BodyKind::#kind }
+        }
+    });
+    let limits = match multipart_fields {
+        Some(multipart_fields) => {
+            let part_limit = match options.part_limit {
+                Some(limit) => quote! { #limit },
+                None => quote! { ::mapping::multipart::MultipartLimits::DEFAULT_PART_LIMIT },
+            };
+            let total_limit = match options.total_limit {
+                Some(limit) => quote! { #limit },
+                None => quote! { ::mapping::multipart::MultipartLimits::DEFAULT_TOTAL_LIMIT },
+            };
+            let field_limits = multipart_fields.iter().filter_map(|field| {
+                let rename = &field.rename;
+                field.limit.map(|limit| quote! { .field(#rename, #limit) })
+            });
+            quote! {
+                ::mapping::multipart::MultipartLimits::new(#part_limit, #total_limit)
+                    #(#field_limits)*
+            }
+        }
+        None => quote! { ::mapping::multipart::MultipartLimits::default() },
+    };
+    quote! {
+        const ACCEPT: &[::mapping::BodyKind] = &[#(#accept),*];
+        let request_body =
+            ::mapping::body::RequestBody::read(cx, &parts, body, ACCEPT, &#limits).await?;
+    }
+}
+
+fn protobuf_deserialize_expanded(field_formats: &Vec<FieldInfo>) -> TokenStream {
+    let mut field_definitions = Vec::new();
+    for field in field_formats {
+        let rename = &field.rename;
+        let name = field.unraw_name();
+        let fty = &field.f_type;
+        let local_ident = local_ident(field);
+        field_definitions.push(quote! {
+            if request_body.kind() == Some(::mapping::BodyKind::Protobuf) {
+                #local_ident = Some(
+                    ::mapping::codec::decode_protobuf::<#fty>(request_body.bytes()).map_err(|e| {
+                        ::mapping::MappingRejection::invalid(::mapping::Source::Protobuf, #rename, #name, e)
+                    })?,
+                );
+            }
+        });
+    }
+    quote! {
+        // protobuf deserialize
+        #(#field_definitions)*
+    }
+}
+
+fn header_deserialize_expanded(field_formats: &Vec<FieldInfo>) -> TokenStream {
+    let mut field_definitions = Vec::new();
+    for field in field_formats {
+        let rename = &field.rename;
+        let name = field.unraw_name();
+        let from_str_parse = from_str_expanded(field, source_expanded(&field.format));
+        let from_header_value = if field.lenient {
+            quote! {
+                if let Ok(v) = v.to_str() {
+                    #from_str_parse
+                }
+            }
+        } else {
+            quote! {
+                let v = v.to_str().map_err(|e| {
+                    ::mapping::MappingRejection::invalid(::mapping::Source::Header, #rename, #name, e)
+                })?;
+                #from_str_parse
+            }
+        };
+        // 多值字段合并同名 header 的所有出现
+        if field.is_vec {
+            field_definitions.push(quote! {
+                for v in parts.headers.get_all(#rename) {
+                    #from_header_value
+                }
+            });
+        } else {
+            field_definitions.push(quote! {
+                if let Some(v) = parts.headers.get(#rename) {
+                    #from_header_value
+                }
+            });
+        }
+    }
+    quote! {
+        // header deserialize
+        #(#field_definitions)*
+    }
+}
+
+fn uri_deserialize_expanded(field_formats: &Vec<FieldInfo>) -> TokenStream {
+    let mut field_definitions = Vec::new();
+    for field in field_formats {
+        let rename = &field.rename;
+        let from_str_parse = from_str_expanded(field, source_expanded(&field.format));
+        field_definitions.push(quote! {
+            #rename => {
+                #from_str_parse
+            }
+        });
+    }
+    quote! {
+        // path deserialize
+        let params = cx.params();
+        for (k, v) in params.iter() {
+            let v = v.as_str();
+            match k.as_str() {
+                #(#field_definitions)*
+                _ => {}
+            }
+        }
+
+    }
+}
+
+fn cookie_deserialize_expanded(field_formats: &Vec<FieldInfo>) -> TokenStream {
+    let mut field_definitions = Vec::new();
+    for field in field_formats {
+        let rename = &field.rename;
+        let name = field.unraw_name();
+        let key = proc_macro2::Literal::byte_string(rename.as_bytes());
+        let local_ident = local_ident(field);
+        let from_str_parse = from_str_expanded(field, source_expanded(&field.format));
+        // 只校验绑定的 cookie 是否为 UTF-8，其他 cookie 不影响请求
+        let to_str = if field.lenient {
+            quote! {
+                let Ok(v) = ::std::str::from_utf8(v) else {
+                    continue;
+                };
+            }
+        } else {
+            quote! {
+                let v = ::std::str::from_utf8(v).map_err(|e| {
+                    ::mapping::MappingRejection::invalid_value(
+                        ::mapping::Source::Cookie,
+                        #rename,
+                        #name,
+                        &String::from_utf8_lossy(v),
+                        e,
+                    )
+                })?;
+            }
+        };
+        // 同名 cookie 单值字段以第一次出现的为准，多值字段收集每一次出现
+        let guard = if field.is_vec {
+            quote! {}
+        } else {
+            quote! { if #local_ident.is_none() }
+        };
+        field_definitions.push(quote! {
+            #key #guard => {
+                #to_str
+                #from_str_parse
+            }
+        });
+    }
+    quote! {
+        // cookie deserialize
+        for (k, v) in ::mapping::cookie::pairs(&parts.headers) {
+            match k {
+                #(#field_definitions)*
+                _ => {}
+            }
+        }
+    }
+}
+
+/// 从 `v: &str` 解析字段值到局部变量，lenient 模式下解析失败时取默认值
+fn from_str_expanded(field: &FieldInfo, source: TokenStream) -> TokenStream {
+    let local_ident = local_ident(field);
+    let rename = &field.rename;
+    let name = field.unraw_name();
+
+    let delimiter = field.delimiter.as_deref().unwrap_or(",");
+    // 多值按分隔符拆分，去掉两端空白并跳过空段
+    let segments = quote! {
+        v.split(#delimiter).map(str::trim).filter(|x| !x.is_empty())
+    };
+
+    let parse = parse_value_expanded(field);
+    if field.lenient {
+        let item = quote! { #parse.unwrap_or_default() };
+        return match (field.is_option, field.is_vec) {
+            (_, true) => quote! {
+                #local_ident
+                    .get_or_insert_with(Vec::new)
+                    .extend(#segments.map(|x| #item));
+            },
+            (true, false) => quote! {
+                let x = v;
+                #local_ident = #parse.ok();
+            },
+            (false, false) => quote! {
+                let x = v;
+                #local_ident = Some(#item);
+            },
+        };
+    }
+
+    let assign = if field.is_vec {
+        quote! {
+            let values = #segments.map(parse).collect::<Result<Vec<_>, _>>()?;
+            #local_ident.get_or_insert_with(Vec::new).extend(values);
+        }
+    } else {
+        quote! { #local_ident = Some(parse(v)?); }
+    };
+    quote! {
+        let parse = |x: &str| {
+            #parse.map_err(|e| {
+                ::mapping::MappingRejection::invalid_value(#source, #rename, #name, x, e)
+            })
+        };
+        #assign
+    }
+}
+
+/// 解析单个值 `x: &str` 的表达式：`with` 指定的函数或 `FromStr`，`try_from` 时再转换为字段类型
+fn parse_value_expanded(field: &FieldInfo) -> TokenStream {
+    let fty = &field.f_type;
+    let decoded = field.try_from.as_ref().unwrap_or(fty);
+    let parse = match &field.parse_with {
+        Some(parse_with) => quote! { #parse_with(x) },
+        None => quote! { x.parse::<#decoded>() },
+    };
+    let Some(raw) = &field.try_from else {
+        return parse;
+    };
+    quote! {
+        #parse
+            .map_err(::std::convert::Into::<volo_http::error::BoxError>::into)
+            .and_then(|raw: #raw| {
+                <#fty as ::std::convert::TryFrom<#raw>>::try_from(raw)
+                    .map_err(::std::convert::Into::into)
+            })
+    }
+}
+
+fn ext_deserialize_expanded(field_formats: &Vec<FieldInfo>) -> TokenStream {
+    let mut field_definitions = Vec::new();
+    for field in field_formats {
+        let local_ident = local_ident(field);
+        let fty = &field.f_type;
+        let value = if field.take {
+            quote!(cx.extensions.remove::<#fty>())
+        } else {
+            quote!(cx.extensions.get::<#fty>().cloned())
+        };
+        field_definitions.push(quote! {
+            #local_ident = #value;
+        });
+    }
+    quote! {
+        #(#field_definitions)*
+    }
+}
+
+/// `#[mapping(from(...))]` 字段依次尝试各来源，记录命中的来源与原始值
+pub fn fallback_expanded(field: &FieldInfo) -> TokenStream {
+    let from_ident = from_ident(field);
+    let name = field.unraw_name();
+    let lenient = field.lenient;
+    let candidates = field.from.iter().map(|(format, rename)| {
+        let source = source_expanded(format);
+        let candidate = FieldInfo {
+            format: format.to_string(),
+            rename: rename.clone(),
+            ..field.clone()
+        };
+        let from_str_parse = from_str_expanded(&candidate, source.clone());
+        // 多值字段收集该来源的所有值，原始值合并后用于冲突比较
+        let (lookup, parse, raw) = if field.is_vec {
+            (
+                quote! {{
+                    let __raw = ::mapping::fallback::lookup_all(
+                        cx, &parts, #source, #rename, #name, #lenient,
+                    )?;
+                    (!__raw.is_empty()).then_some(__raw)
+                }},
+                quote! {
+                    for v in __raw.iter() {
+                        let v: &str = v;
+                        #from_str_parse
+                    }
+                },
+                quote! { __raw.join("&") },
+            )
+        } else {
+            (
+                quote! {
+                    ::mapping::fallback::lookup(cx, &parts, #source, #rename, #name, #lenient)?
+                },
+                quote! {
+                    let v: &str = &__raw;
+                    #from_str_parse
+                },
+                quote! { __raw.to_string() },
+            )
+        };
+        if field.reject_conflict {
+            quote! {
+                if let Some(__raw) = #lookup {
+                    let __joined = #raw;
+                    match &#from_ident {
+                        None => {
+                            let __raw = &__raw;
+                            #parse
+                            #from_ident = Some((#source, #rename, __joined));
+                        }
+                        Some((first_source, first_name, first)) if *first != __joined => {
+                            return Err(::mapping::MappingRejection::conflict(
+                                #name,
+                                (*first_source, *first_name),
+                                (#source, #rename),
+                            ));
+                        }
+                        Some(_) => {}
+                    }
+                }
+            }
+        } else {
+            quote! {
+                if #from_ident.is_none() {
+                    if let Some(__raw) = #lookup {
+                        {
+                            let __raw = &__raw;
+                            #parse
+                        }
+                        #from_ident = Some((#source, #rename, #raw));
+                    }
+                }
+            }
+        }
+    });
+    quote! {
+        // fallback deserialize
+        let mut #from_ident: Option<(::mapping::Source, &'static str, String)> = None;
+        #(#candidates)*
+    }
+}
+
+/// header/uri/ext 字段先读入 `Option` 局部变量，全部来源处理完后再检查是否缺失
+pub fn local_declare_expanded(field: &FieldInfo) -> TokenStream {
+    let local_ident = local_ident(field);
+    let fty = &field.f_type;
+    let local_ty = if field.is_vec {
+        quote! { Vec<#fty> }
+    } else {
+        quote! { #fty }
+    };
+    quote! {
+        let mut #local_ident: Option<#local_ty> = None;
+    }
+}
+
+/// `#[mapping(flatten)]` 字段经由 `FromParts` 提取
+pub fn flatten_expanded(field: &FieldInfo) -> TokenStream {
+    let local_ident = local_ident(field);
+    let fty = &field.f_type;
+    quote! {
+        let #local_ident = <#fty as ::mapping::FromParts>::from_parts(cx, &parts).await?;
+    }
+}
+
+/// 字段上 `#[validate(...)]` 的校验，检查构造前的字段值，失败信息收集到 `__errors`
+pub fn validate_expanded(field: &FieldInfo) -> TokenStream {
+    let rename = &field.rename;
+    let name = field.unraw_name();
+    let source = source_expanded(field.source_format());
+    let value_ident = value_ident(field);
+    let value = quote! { &#value_ident };
+    let bound = |bound: &Option<TokenStream>| match bound {
+        Some(bound) => quote! { Some(#bound) },
+        None => quote! { None },
+    };
+    let checks = field.validators.iter().map(|validator| {
+        let check = match validator {
+            Validator::Range { min, max } => {
+                let (min, max) = (bound(min), bound(max));
+                quote! { ::mapping::validate::range(#value, #min, #max) }
+            }
+            Validator::Length { min, max } => {
+                let (min, max) = (bound(min), bound(max));
+                quote! { ::mapping::validate::length(#value, #min, #max) }
+            }
+            Validator::Regex(pattern) => quote! {{
+                static REGEX: ::std::sync::LazyLock<::mapping::validate::Regex> =
+                    ::std::sync::LazyLock::new(|| ::mapping::validate::Regex::new(#pattern).unwrap());
+                ::mapping::validate::regex(#value, &REGEX)
+            }},
+            Validator::Email => quote! { ::mapping::validate::email(#value) },
+            Validator::Custom(custom) => quote! { ::mapping::validate::custom(#custom(#value)) },
+        };
+        quote! {
+            if let Err(message) = #check {
+                __errors.push(::mapping::ValidationError::new(#source, #rename, #name, message));
+            }
+        }
+    });
+    quote! { #(#checks)* }
+}
+
+/// 构造 `Self` 时字段的取值表达式
+pub fn field_value_expanded(field: &FieldInfo) -> TokenStream {
+    let field_name_ident = format_ident!("{}", field.name);
+    if field.format == "flatten" {
+        let local_ident = local_ident(field);
+        return quote! { #local_ident };
+    }
+    let rename = &field.rename;
+    let name = field.unraw_name();
+    let source = source_expanded(field.source_format());
+    let fty = &field.f_type;
+    if !field.is_local() {
+        let mode = mode_ident(&field.format);
+        let value = quote! { #mode.#field_name_ident };
+        let Some(raw) = &field.try_from else {
+            return value;
+        };
+        // `try_from` 字段由原始类型转换为字段类型，失败时拒绝请求
+        let convert = quote! { <#fty as ::std::convert::TryFrom<#raw>>::try_from };
+        let rejection = quote! {
+            |e| ::mapping::MappingRejection::invalid(#source, #rename, #name, e)
+        };
+        return if field.is_option {
+            quote! { #value.map(#convert).transpose().map_err(#rejection)? }
+        } else {
+            quote! { #convert(#value).map_err(#rejection)? }
+        };
+    }
+
+    let local_ident = local_ident(field);
+    // 多值先收集为 `Vec`，再转换为字段的集合类型
+    let value = match &field.collection {
+        None => quote! { #local_ident },
+        Some(Collection::Collect(collection)) => quote! {
+            #local_ident.map(|v| v.into_iter().collect::<#collection>())
+        },
+        Some(Collection::Array(len)) if field.lenient => quote! {
+            #local_ident.and_then(|v| <[#fty; #len]>::try_from(v).ok())
+        },
+        Some(Collection::Array(len)) => quote! {
+            #local_ident
+                .map(|v| {
+                    let found = v.len();
+                    <[#fty; #len]>::try_from(v).map_err(|_| {
+                        ::mapping::MappingRejection::invalid(
+                            #source,
+                            #rename,
+                            #name,
+                            format!("expected {} values, found {}", #len, found),
+                        )
+                    })
+                })
+                .transpose()?
+        },
+    };
+    // `Sourced<T>` 带上命中的来源
+    let value = if field.is_sourced {
+        let from_ident = from_ident(field);
+        quote! {
+            match (#value, #from_ident) {
+                (Some(value), Some((source, name, _))) => {
+                    Some(::mapping::Sourced { value, source, name })
+                }
+                _ => None,
+            }
+        }
+    } else {
+        value
+    };
+    if field.is_option {
+        quote! { #value }
+    } else if let Some(default) = &field.default {
+        quote! { #value.unwrap_or_else(|| #default) }
+    } else {
+        quote! {
+            #value.ok_or_else(|| ::mapping::MappingRejection::missing(#source, #rename, #name))?
+        }
+    }
+}
+
+// 生成的局部变量按种类使用互不重叠的前缀，任何字段名都不会与之冲突
+fn mode_ident(format: &str) -> Ident {
+    format_ident!("__mapping_mode_{}", format)
+}
+
+pub fn local_ident(field: &FieldInfo) -> Ident {
+    field_local_ident(field.unraw_name())
+}
+
+pub fn field_local_ident(name: &str) -> Ident {
+    format_ident!("__mapping_field_{}", name)
+}
+
+/// 字段的最终取值，校验后用于构造
+pub fn value_ident(field: &FieldInfo) -> Ident {
+    format_ident!("__mapping_value_{}", field.unraw_name())
+}
+
+fn from_ident(field: &FieldInfo) -> Ident {
+    format_ident!("__mapping_from_{}", field.unraw_name())
+}
+
+pub fn source_expanded(format: &str) -> TokenStream {
+    use heck::ToUpperCamelCase;
+    let variant = format_ident!("{}", format.to_upper_camel_case());
+    quote! { ::mapping::Source::#variant }
+}
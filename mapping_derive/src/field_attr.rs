@@ -0,0 +1,995 @@
+use super::helper::*;
+use faststr::FastStr;
+use proc_macro2::TokenStream;
+use quote::{ToTokens, quote};
+use syn::{
+    Attribute, Error,
+    Expr::{self},
+    Field, LitStr, Meta, MetaList, MetaNameValue, Token, bracketed,
+    ext::IdentExt,
+    parse::{Parse, ParseStream, Parser},
+    punctuated::Punctuated,
+};
+
+#[derive(Default, Clone)]
+pub struct FieldInfo {
+    pub name: String,
+    pub f_type: TokenStream,
+    pub format: String,
+    pub serde: Option<TokenStream>,
+    pub rename: String,
+    pub is_option: bool,
+    pub is_vec: bool,
+    pub lenient: bool,
+    /// 字段缺失时使用的默认值表达式
+    pub default: Option<TokenStream>,
+    /// multipart 文件字段
+    pub is_file: bool,
+    /// multipart 单个 part 的大小限制
+    pub limit: Option<u64>,
+    /// `#[body(codec = ...)]` 指定的解码器
+    pub codec: Option<TokenStream>,
+    /// `#[query(style = "comma")]` 多值参数的写法
+    pub style: Option<String>,
+    /// header/uri/cookie 多值字段的分隔符，默认为 `,`
+    pub delimiter: Option<String>,
+    /// 多值字段不是 `Vec` 时的目标集合
+    pub collection: Option<Collection>,
+    /// `#[header(with = path::parse_fn)]` 解析单个值的函数
+    pub parse_with: Option<TokenStream>,
+    /// `#[mapping(try_from = "Raw")]` 先读取的原始类型
+    pub try_from: Option<TokenStream>,
+    /// `#[mapping(from(...))]` 依次尝试的来源与名称
+    pub from: Vec<(String, String)>,
+    /// 多个来源的值不一致时拒绝请求
+    pub reject_conflict: bool,
+    /// 字段类型为 `Sourced<T>`，记录命中的来源
+    pub is_sourced: bool,
+    /// `#[ext(take)]` 从上下文中移出扩展而不是克隆
+    pub take: bool,
+    /// 字段的文档注释，用作 OpenAPI 描述
+    pub doc: Option<String>,
+    /// `#[validate(...)]` 校验规则
+    pub validators: Vec<Validator>,
+}
+
+/// `#[validate(...)]` 中的单条校验规则
+#[derive(Clone)]
+pub enum Validator {
+    Range {
+        min: Option<TokenStream>,
+        max: Option<TokenStream>,
+    },
+    Length {
+        min: Option<TokenStream>,
+        max: Option<TokenStream>,
+    },
+    Regex(String),
+    Email,
+    Custom(TokenStream),
+}
+
+impl FieldInfo {
+    /// 是否先读入局部变量，而不是经由 `XxxMode` 结构体反序列化
+    pub fn is_local(&self) -> bool {
+        OPTION_FORMATS.contains(&self.format.as_str())
+            || self.is_file
+            || self.format == "protobuf"
+            || self.format == "from"
+    }
+
+    /// 去掉 `r#` 前缀的字段名，用于生成的局部变量与拒绝信息
+    pub fn unraw_name(&self) -> &str {
+        self.name.strip_prefix("r#").unwrap_or(&self.name)
+    }
+
+    /// 缺失与校验失败时报告的来源，`from` 字段为其第一个来源
+    pub fn source_format(&self) -> &str {
+        match self.from.first() {
+            Some((format, _)) => format,
+            None => &self.format,
+        }
+    }
+}
+
+/// `#[mapping(...)]` 选项，结构体上的作为所有字段的默认值
+#[derive(Default, Clone)]
+pub struct MappingOptions {
+    pub lenient: bool,
+    /// 字段类型本身 derive 了 Mapping，合并其绑定，仅字段上可用
+    pub flatten: bool,
+    /// multipart 单个 part 的大小限制
+    pub part_limit: Option<u64>,
+    /// multipart 整个 body 的大小限制
+    pub total_limit: Option<u64>,
+    /// 允许的请求体类型，仅结构体上可用
+    pub accept: Option<Vec<String>>,
+    /// 枚举的分派字段，如 `tag = header("x-api-version")`，仅枚举上可用
+    pub tag: Option<TagInfo>,
+    /// 枚举变体对应的分派值，仅变体上可用
+    pub rename: Option<String>,
+    /// 各来源的字段命名规则，如 `header(rename_all = "kebab-case")`
+    pub rename_all: Vec<(String, RenameRule)>,
+    /// 先读取为该类型再经 `TryFrom` 转换，仅字段上可用
+    pub try_from: Option<TokenStream>,
+    /// 依次尝试的来源，名称缺省时按字段名，仅字段上可用
+    pub from: Option<Vec<(String, Option<String>)>>,
+    /// `conflict = "reject"`：`from` 的多个来源值不一致时拒绝请求
+    pub reject_conflict: bool,
+    /// 同时生成客户端的 `IntoRequest`，仅结构体上可用
+    pub client: bool,
+    /// 结构体对应的路由模板，与 `#[uri]` 字段互相校验，仅结构体上可用
+    pub path: Option<String>,
+}
+
+impl MappingOptions {
+    /// 未显式重命名的字段在 `format` 来源中使用的名称
+    pub fn default_rename(&self, format: &str, field_name: &str) -> String {
+        self.rename_all
+            .iter()
+            .rfind(|(rule_format, _)| rule_format == format)
+            .map_or_else(
+                || field_name.to_string(),
+                |(_, rule)| rule.apply(field_name),
+            )
+    }
+}
+
+/// `rename_all` 支持的命名规则，与 serde 一致
+#[derive(Clone, Copy, PartialEq)]
+pub enum RenameRule {
+    Lower,
+    Upper,
+    Pascal,
+    Camel,
+    Snake,
+    ScreamingSnake,
+    Kebab,
+    ScreamingKebab,
+}
+
+impl RenameRule {
+    const RULES: &[(&str, RenameRule)] = &[
+        ("lowercase", RenameRule::Lower),
+        ("UPPERCASE", RenameRule::Upper),
+        ("PascalCase", RenameRule::Pascal),
+        ("camelCase", RenameRule::Camel),
+        ("snake_case", RenameRule::Snake),
+        ("SCREAMING_SNAKE_CASE", RenameRule::ScreamingSnake),
+        ("kebab-case", RenameRule::Kebab),
+        ("SCREAMING-KEBAB-CASE", RenameRule::ScreamingKebab),
+    ];
+
+    fn from_lit(lit_str: &LitStr) -> Result<Self, Error> {
+        let value = lit_str.value();
+        Self::RULES
+            .iter()
+            .find(|(name, _)| *name == value)
+            .map(|(_, rule)| *rule)
+            .ok_or_else(|| {
+                let names: Vec<_> = Self::RULES.iter().map(|(name, _)| *name).collect();
+                Error::new_spanned(
+                    lit_str,
+                    format!("unknown rename rule, expected one of: {}", names.join(", ")),
+                )
+            })
+    }
+
+    pub fn apply(self, field_name: &str) -> String {
+        use heck::{
+            ToKebabCase, ToLowerCamelCase, ToShoutyKebabCase, ToShoutySnakeCase, ToSnakeCase,
+            ToUpperCamelCase,
+        };
+        match self {
+            RenameRule::Lower => field_name.to_lowercase(),
+            RenameRule::Upper => field_name.to_uppercase(),
+            RenameRule::Pascal => field_name.to_upper_camel_case(),
+            RenameRule::Camel => field_name.to_lower_camel_case(),
+            RenameRule::Snake => field_name.to_snake_case(),
+            RenameRule::ScreamingSnake => field_name.to_shouty_snake_case(),
+            RenameRule::Kebab => field_name.to_kebab_case(),
+            RenameRule::ScreamingKebab => field_name.to_shouty_kebab_case(),
+        }
+    }
+}
+
+/// 枚举分派字段的来源与名称
+#[derive(Clone, PartialEq)]
+pub struct TagInfo {
+    pub format: String,
+    pub name: String,
+}
+
+// 可以作为枚举分派字段的来源
+pub const TAG_FORMATS: &[&str] = &[
+    "header", "uri", "query", "cookie", "json", "form", "msgpack", "cbor", "xml",
+];
+
+// 可以作为请求体的格式
+pub const BODY_FORMATS: &[&str] = &[
+    "json",
+    "form",
+    "msgpack",
+    "cbor",
+    "xml",
+    "protobuf",
+    "body",
+    "multipart",
+];
+
+// `#[mapping(from(...))]` 可以使用的来源
+pub const FROM_FORMATS: &[&str] = &["header", "uri", "query", "cookie"];
+
+// `#[query(style = ...)]` 支持的多值写法
+pub const QUERY_STYLES: &[&str] = &["repeat", "comma", "brackets"];
+
+// 可以使用 `rename_all` 的来源
+pub const RENAME_ALL_FORMATS: &[&str] = &[
+    "json",
+    "form",
+    "msgpack",
+    "cbor",
+    "xml",
+    "body",
+    "multipart",
+    "uri",
+    "query",
+    "header",
+    "cookie",
+];
+
+// 定义属性优先级顺序
+pub const FORMATS: &[&str] = &[
+    "ext",
+    "json",
+    "form",
+    "msgpack",
+    "cbor",
+    "xml",
+    "protobuf",
+    "body",
+    // multipart 会消费请求体，必须排在其他请求体格式之后
+    "multipart",
+    "uri",
+    "query",
+    "header",
+    "cookie",
+];
+
+/// 优先从指定属性获取字段名，如果没有则返回字段本身名称
+pub fn get_field_info(
+    struct_format: &str,
+    struct_options: &MappingOptions,
+    field: &Field,
+) -> Result<FieldInfo, Error> {
+    let (serde_attr, rename) = serde_indent(field);
+    let options = get_mapping_options(&field.attrs, struct_options)?;
+    let validators = get_validators(&field.attrs)?;
+    if options.accept != struct_options.accept
+        || options.tag != struct_options.tag
+        || options.rename != struct_options.rename
+        || options.rename_all != struct_options.rename_all
+    {
+        return Err(Error::new_spanned(
+            field,
+            "`accept`, `tag`, `rename` and source options are not allowed on fields",
+        ));
+    }
+    if options.client != struct_options.client || options.path != struct_options.path {
+        return Err(Error::new_spanned(
+            field,
+            "`client` and `path` are only allowed on structs",
+        ));
+    }
+    if options.flatten {
+        if let Some(attr) = field
+            .attrs
+            .iter()
+            .find(|attr| attr.path().is_ident("validate"))
+        {
+            return Err(Error::new_spanned(
+                attr,
+                "#[mapping(flatten)] fields are validated by their own type",
+            ));
+        }
+        if let Some(attr) = field.attrs.iter().find(|attr| {
+            FORMATS
+                .iter()
+                .any(|attr_name| attr.path().is_ident(attr_name))
+        }) {
+            return Err(Error::new_spanned(
+                attr,
+                "#[mapping(flatten)] fields cannot have a source attribute",
+            ));
+        }
+        if options.try_from.is_some() {
+            return Err(Error::new_spanned(
+                field,
+                "`try_from` cannot be combined with `flatten`",
+            ));
+        }
+        let field_name = field.ident.as_ref().unwrap().to_string();
+        let field_type = &field.ty;
+        return Ok(FieldInfo {
+            name: field_name.clone(),
+            f_type: quote!(#field_type),
+            format: "flatten".to_string(),
+            rename: field_name,
+            ..Default::default()
+        });
+    }
+    if let Some(from) = &options.from {
+        return from_field_info(field, from, &options, validators);
+    }
+    let mut source_attrs = field.attrs.iter().filter(|attr| {
+        FORMATS
+            .iter()
+            .any(|attr_name| attr.path().is_ident(attr_name))
+    });
+    if let (Some(_), Some(duplicate)) = (source_attrs.next(), source_attrs.next()) {
+        return Err(Error::new_spanned(
+            duplicate,
+            "a field can only have one source attribute",
+        ));
+    }
+    for attr_name in FORMATS {
+        if let Some(mut column) = attr_field_info(field, attr_name, &options)? {
+            let (is_option, is_vec, f_type) = composite_type(column.format.as_str(), &field.ty);
+            column.serde = rename_serde_attr(serde_attr, rename.is_some(), &column);
+            if let Some(rename) = rename {
+                column.rename = rename;
+            }
+            column.is_option = is_option;
+            column.is_vec = is_vec;
+            column.f_type = f_type;
+            column.collection = collection_type(&column.format, &field.ty);
+            column.lenient = options.lenient;
+            column.is_file = column.format == "multipart" && is_upload_file_type(&field.ty);
+            column.validators = validators;
+            return with_try_from(column, field, &options);
+        }
+    }
+    let field_name = field.ident.as_ref().unwrap().to_string();
+    let wire_name = field.ident.as_ref().unwrap().unraw().to_string();
+    let (is_option, is_vec, f_type) = composite_type(struct_format, &field.ty);
+    // 默认返回字段标识符
+    let mut column = FieldInfo {
+        name: field_name.clone(),
+        f_type,
+        format: struct_format.to_string(),
+        rename: options.default_rename(struct_format, &wire_name),
+        is_option,
+        is_vec,
+        lenient: options.lenient,
+        is_file: struct_format == "multipart" && is_upload_file_type(&field.ty),
+        collection: collection_type(struct_format, &field.ty),
+        validators,
+        ..Default::default()
+    };
+    column.serde = rename_serde_attr(serde_attr, rename.is_some(), &column);
+    if let Some(rename) = rename {
+        column.rename = rename;
+    }
+    with_try_from(column, field, &options)
+}
+
+/// `#[mapping(from(...))]` 字段依次从多个来源读取，按 header 的方式解析
+fn from_field_info(
+    field: &Field,
+    from: &[(String, Option<String>)],
+    options: &MappingOptions,
+    validators: Vec<Validator>,
+) -> Result<FieldInfo, Error> {
+    if let Some(attr) = field.attrs.iter().find(|attr| {
+        FORMATS
+            .iter()
+            .any(|attr_name| attr.path().is_ident(attr_name))
+    }) {
+        return Err(Error::new_spanned(
+            attr,
+            "#[mapping(from(...))] fields cannot have a source attribute",
+        ));
+    }
+    let field_name = field.ident.as_ref().unwrap().to_string();
+    let wire_name = field.ident.as_ref().unwrap().unraw().to_string();
+    let from: Vec<_> = from
+        .iter()
+        .map(|(format, name)| {
+            let name = name
+                .clone()
+                .unwrap_or_else(|| options.default_rename(format, &wire_name));
+            (format.clone(), name)
+        })
+        .collect();
+    // `Sourced<T>` 与 `Option<Sourced<T>>` 额外记录命中的来源
+    let is_option = is_option_type(&field.ty);
+    let value_ty = get_option_inner_type(&field.ty).unwrap_or_else(|| field.ty.clone());
+    let sourced_ty = get_inner_type("Sourced", &value_ty);
+    let value_ty = sourced_ty.clone().unwrap_or(value_ty);
+    let (_, is_vec, f_type) = composite_type("header", &value_ty);
+    let column = FieldInfo {
+        name: field_name,
+        f_type,
+        format: "from".to_string(),
+        rename: from[0].1.clone(),
+        is_option,
+        is_vec,
+        lenient: options.lenient,
+        collection: collection_type("header", &value_ty),
+        validators,
+        from,
+        reject_conflict: options.reject_conflict,
+        is_sourced: sourced_ty.is_some(),
+        ..Default::default()
+    };
+    with_try_from(column, field, options)
+}
+
+/// `#[mapping(try_from = "Raw")]`：按 `Raw` 读取后经 `TryFrom` 转换为字段类型
+fn with_try_from(
+    mut column: FieldInfo,
+    field: &Field,
+    options: &MappingOptions,
+) -> Result<FieldInfo, Error> {
+    let Some(raw) = &options.try_from else {
+        return Ok(column);
+    };
+    if column.format == "ext" || column.format == "protobuf" || column.is_file {
+        return Err(Error::new_spanned(
+            field,
+            format!("`try_from` is not supported on #[{}] fields", column.format),
+        ));
+    }
+    // serde 来源的 `Option<T>` 字段读取为 `Option<Raw>`，再逐个转换
+    if !column.is_local()
+        && let Some(inner) = get_option_inner_type(&field.ty)
+    {
+        column.is_option = true;
+        column.f_type = inner.to_token_stream();
+    }
+    column.try_from = Some(raw.clone());
+    Ok(column)
+}
+
+/// 字段名来自来源属性或 `rename_all` 时，经由 `XxxMode` 反序列化也要使用该名称
+fn rename_serde_attr(
+    serde_attr: Option<TokenStream>,
+    serde_renamed: bool,
+    column: &FieldInfo,
+) -> Option<TokenStream> {
+    if serde_renamed || column.rename == column.name {
+        return serde_attr;
+    }
+    let rename = &column.rename;
+    Some(quote! {
+        #serde_attr
+        #[serde(rename = #rename)]
+    })
+}
+
+/// 解析 `#[mapping(lenient)]`，未出现的选项沿用 `defaults`
+pub fn get_mapping_options(
+    attrs: &[Attribute],
+    defaults: &MappingOptions,
+) -> Result<MappingOptions, Error> {
+    let mut options = defaults.clone();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("mapping")) {
+        let nested = attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)?;
+        for meta in nested {
+            match &meta {
+                Meta::Path(path) if path.is_ident("lenient") => options.lenient = true,
+                Meta::Path(path) if path.is_ident("flatten") => options.flatten = true,
+                Meta::Path(path) if path.is_ident("client") => options.client = true,
+                // #[mapping(header(rename_all = "kebab-case"), multipart(part_limit = 1048576))]
+                Meta::List(ml)
+                    if RENAME_ALL_FORMATS
+                        .iter()
+                        .any(|format| ml.path.is_ident(format)) =>
+                {
+                    let format = ml.path.to_token_stream().to_string();
+                    let nested = ml.parse_args_with(
+                        Punctuated::<MetaNameValue, Token![,]>::parse_terminated,
+                    )?;
+                    for nv in nested {
+                        if nv.path.is_ident("rename_all") {
+                            let lit_str = meta_name_value_lit(&nv).ok_or_else(|| {
+                                Error::new_spanned(&nv.value, "expected a string literal")
+                            })?;
+                            let rule = RenameRule::from_lit(lit_str)?;
+                            options.rename_all.push((format.clone(), rule));
+                        } else if format == "multipart" && nv.path.is_ident("part_limit") {
+                            options.part_limit = Some(meta_name_value_int(&nv)?);
+                        } else if format == "multipart" && nv.path.is_ident("total_limit") {
+                            options.total_limit = Some(meta_name_value_int(&nv)?);
+                        } else {
+                            return Err(Error::new_spanned(
+                                nv.path,
+                                format!("unknown {format} option"),
+                            ));
+                        }
+                    }
+                }
+                // #[mapping(accept = ["json", "form"])]
+                Meta::NameValue(nv) if nv.path.is_ident("accept") => {
+                    options.accept = Some(meta_name_value_accept(nv)?);
+                }
+                // #[mapping(tag = header("x-api-version"))]
+                Meta::NameValue(nv) if nv.path.is_ident("tag") => {
+                    options.tag = Some(meta_name_value_tag(nv)?);
+                }
+                // 字段上的 #[mapping(try_from = "String")]
+                Meta::NameValue(nv) if nv.path.is_ident("try_from") => {
+                    let lit_str = meta_name_value_lit(nv).ok_or_else(|| {
+                        Error::new_spanned(&nv.value, "expected a string literal")
+                    })?;
+                    let raw = lit_str.parse::<syn::Type>()?;
+                    options.try_from = Some(quote!(#raw));
+                }
+                // 字段上的 #[mapping(from(header("x-api-key"), query("api_key")))]
+                Meta::List(ml) if ml.path.is_ident("from") => {
+                    options.from = Some(meta_list_from(ml)?);
+                }
+                // #[mapping(conflict = "reject")]
+                Meta::NameValue(nv) if nv.path.is_ident("conflict") => {
+                    let lit_str = meta_name_value_lit(nv).ok_or_else(|| {
+                        Error::new_spanned(&nv.value, "expected a string literal")
+                    })?;
+                    options.reject_conflict = match lit_str.value().as_str() {
+                        "first" => false,
+                        "reject" => true,
+                        _ => {
+                            return Err(Error::new_spanned(
+                                lit_str,
+                                "unknown conflict policy, expected one of: first, reject",
+                            ));
+                        }
+                    };
+                }
+                // #[mapping(path = "/users/{uid}")]
+                Meta::NameValue(nv) if nv.path.is_ident("path") => {
+                    let lit_str = meta_name_value_lit(nv).ok_or_else(|| {
+                        Error::new_spanned(&nv.value, "expected a string literal")
+                    })?;
+                    options.path = Some(lit_str.value());
+                }
+                // 变体上的 #[mapping(rename = "v1")]
+                Meta::NameValue(nv) if nv.path.is_ident("rename") => {
+                    let lit_str = meta_name_value_lit(nv).ok_or_else(|| {
+                        Error::new_spanned(&nv.value, "expected a string literal")
+                    })?;
+                    options.rename = Some(lit_str.value());
+                }
+                _ => return Err(Error::new_spanned(meta, "unknown mapping option")),
+            }
+        }
+    }
+    Ok(options)
+}
+fn meta_name_value_tag(nv: &MetaNameValue) -> Result<TagInfo, Error> {
+    let parser = |input: ParseStream| {
+        let format: syn::Ident = input.parse()?;
+        let content;
+        syn::parenthesized!(content in input);
+        let name: LitStr = content.parse()?;
+        Ok((format, name))
+    };
+    let (format, name) = parser.parse2(nv.value.to_token_stream()).map_err(|e| {
+        Error::new(
+            e.span(),
+            "expected a tag such as `header(\"x-api-version\")`",
+        )
+    })?;
+    let format = format.to_string();
+    if !TAG_FORMATS.contains(&format.as_str()) {
+        return Err(Error::new_spanned(
+            &nv.value,
+            format!(
+                "unsupported tag source, expected one of: {}",
+                TAG_FORMATS.join(", ")
+            ),
+        ));
+    }
+    Ok(TagInfo {
+        format,
+        name: name.value(),
+    })
+}
+
+fn meta_list_from(ml: &MetaList) -> Result<Vec<(String, Option<String>)>, Error> {
+    let nested = ml.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)?;
+    let mut from = Vec::new();
+    for meta in nested {
+        let (path, name) = match &meta {
+            Meta::Path(path) => (path, None),
+            Meta::List(ml) => (&ml.path, Some(ml.parse_args::<LitStr>()?.value())),
+            Meta::NameValue(_) => (meta.path(), None),
+        };
+        let format = path.to_token_stream().to_string();
+        if matches!(meta, Meta::NameValue(_)) || !FROM_FORMATS.contains(&format.as_str()) {
+            return Err(Error::new_spanned(
+                meta,
+                format!(
+                    "expected a source such as `header(\"x-api-key\")`, one of: {}",
+                    FROM_FORMATS.join(", ")
+                ),
+            ));
+        }
+        from.push((format, name));
+    }
+    if from.is_empty() {
+        return Err(Error::new_spanned(ml, "expected at least one source"));
+    }
+    Ok(from)
+}
+
+fn meta_name_value_accept(nv: &MetaNameValue) -> Result<Vec<String>, Error> {
+    let parser = |input: ParseStream| {
+        let content;
+        bracketed!(content in input);
+        Punctuated::<LitStr, Token![,]>::parse_terminated(&content)
+    };
+    let formats = parser
+        .parse2(nv.value.to_token_stream())
+        .map_err(|e| Error::new(e.span(), "expected an array of body formats"))?;
+    let mut accept = Vec::new();
+    for lit_str in formats {
+        let format = lit_str.value();
+        if !BODY_FORMATS.contains(&format.as_str()) {
+            return Err(Error::new_spanned(
+                lit_str,
+                format!(
+                    "unknown body format, expected one of: {}",
+                    BODY_FORMATS.join(", ")
+                ),
+            ));
+        }
+        if !accept.contains(&format) {
+            accept.push(format);
+        }
+    }
+    Ok(accept)
+}
+
+/// 解析 `#[validate(range(min = 1, max = 100), length(max = 32), regex = "..", email, custom = path)]`
+fn get_validators(attrs: &[Attribute]) -> Result<Vec<Validator>, Error> {
+    let mut validators = Vec::new();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("validate")) {
+        let nested = attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)?;
+        for meta in nested {
+            let validator = match &meta {
+                Meta::List(ml) if ml.path.is_ident("range") => {
+                    let (min, max) = validator_bounds(ml, false)?;
+                    Validator::Range { min, max }
+                }
+                Meta::List(ml) if ml.path.is_ident("length") => {
+                    let (min, max) = validator_bounds(ml, true)?;
+                    Validator::Length { min, max }
+                }
+                Meta::NameValue(nv) if nv.path.is_ident("regex") => {
+                    let lit_str = meta_name_value_lit(nv).ok_or_else(|| {
+                        Error::new_spanned(&nv.value, "expected a string literal")
+                    })?;
+                    let pattern = lit_str.value();
+                    if let Err(e) = regex::Regex::new(&pattern) {
+                        return Err(Error::new_spanned(lit_str, e));
+                    }
+                    Validator::Regex(pattern)
+                }
+                Meta::Path(path) if path.is_ident("email") => Validator::Email,
+                Meta::NameValue(nv) if nv.path.is_ident("custom") => {
+                    let custom = &nv.value;
+                    Validator::Custom(quote!(#custom))
+                }
+                _ => return Err(Error::new_spanned(meta, "unknown validate rule")),
+            };
+            validators.push(validator);
+        }
+    }
+    Ok(validators)
+}
+
+/// 解析 `range(min = .., max = ..)` 或 `length(min = .., max = .., equal = ..)`
+fn validator_bounds(
+    ml: &MetaList,
+    allow_equal: bool,
+) -> Result<(Option<TokenStream>, Option<TokenStream>), Error> {
+    let nested = ml.parse_args_with(Punctuated::<MetaNameValue, Token![,]>::parse_terminated)?;
+    let (mut min, mut max) = (None, None);
+    for nv in nested {
+        let value = &nv.value;
+        if nv.path.is_ident("min") {
+            min = Some(quote!(#value));
+        } else if nv.path.is_ident("max") {
+            max = Some(quote!(#value));
+        } else if allow_equal && nv.path.is_ident("equal") {
+            min = Some(quote!(#value));
+            max = Some(quote!(#value));
+        } else {
+            return Err(Error::new_spanned(nv.path, "unknown bound"));
+        }
+    }
+    if min.is_none() && max.is_none() {
+        return Err(Error::new_spanned(ml, "expected `min` or `max`"));
+    }
+    Ok((min, max))
+}
+
+pub fn get_default_format(attrs: &[Attribute]) -> Result<FastStr, Error> {
+    let mut default_attrs = attrs.iter().filter(|attr| attr.path().is_ident("format"));
+    let Some(attr) = default_attrs.next() else {
+        return Ok(FastStr::from("json"));
+    };
+    if let Some(duplicate) = default_attrs.next() {
+        return Err(Error::new_spanned(
+            duplicate,
+            "duplicate #[format] attribute",
+        ));
+    }
+
+    match &attr.meta {
+        // 情况1: #[format]
+        Meta::Path(_) => Ok(FastStr::from("json")),
+
+        // 情况2: #[format = "json"]
+        Meta::NameValue(nv) => {
+            let lit_str = meta_name_value_lit(nv)
+                .ok_or_else(|| Error::new_spanned(&nv.value, "expected a string literal"))?;
+            validate_format(lit_str)
+        }
+
+        // 情况3: #[format("json")]、#[format(format = "json")] 或 #[format(format("json"))]
+        Meta::List(ml) => {
+            if let Ok(lit_str) = ml.parse_args::<LitStr>() {
+                return validate_format(&lit_str);
+            }
+            let meta = ml.parse_args::<Meta>().map_err(|e| {
+                Error::new(
+                    e.span(),
+                    "expected `#[format(\"json\")]` or `#[format = \"json\"]`",
+                )
+            })?;
+            let lit_str = match &meta {
+                Meta::NameValue(nv) if nv.path.is_ident("format") => meta_name_value_lit(nv)
+                    .cloned()
+                    .ok_or_else(|| Error::new_spanned(&nv.value, "expected a string literal"))?,
+                Meta::List(ml) if ml.path.is_ident("format") => ml.parse_args::<LitStr>()?,
+                _ => return Err(Error::new_spanned(meta, "unknown format option")),
+            };
+            validate_format(&lit_str)
+        }
+    }
+}
+
+fn attr_field_info(
+    field: &Field,
+    attr_name: &str,
+    options: &MappingOptions,
+) -> Result<Option<FieldInfo>, Error> {
+    let Some(field_name) = field.ident.as_ref() else {
+        return Ok(None);
+    };
+    let Some(attr) = field
+        .attrs
+        .iter()
+        .find(|attr| attr.path().is_ident(attr_name))
+    else {
+        return Ok(None);
+    };
+
+    let field_type = &field.ty;
+    let mut field_format = FieldInfo {
+        name: field_name.to_string(),
+        f_type: quote!(#field_type),
+        format: attr_name.to_string(),
+        rename: options.default_rename(attr_name, &field_name.unraw().to_string()),
+        ..Default::default()
+    };
+
+    // 处理 #[query] 无参数属性
+    if let Meta::Path(_) = attr.meta {
+        return Ok(Some(field_format));
+    }
+
+    let args = attr.parse_args_with(Punctuated::<AttrArg, Token![,]>::parse_terminated)?;
+    for arg in args {
+        match arg {
+            // 处理 #[header("value")] 直接字面量
+            AttrArg::Lit(lit_str) => field_format.rename = lit_str.value(),
+            AttrArg::Meta(meta) => match meta.as_ref() {
+                // 处理 rename = "value" 命名值形式
+                Meta::NameValue(nv) if nv.path.is_ident("rename") => {
+                    let lit_str = meta_name_value_lit(nv).ok_or_else(|| {
+                        Error::new_spanned(&nv.value, "expected a string literal")
+                    })?;
+                    field_format.rename = lit_str.value();
+                }
+                // 处理 rename("value") 列表形式
+                Meta::List(ml) if ml.path.is_ident("rename") => {
+                    field_format.rename = ml.parse_args::<LitStr>()?.value();
+                }
+                // 处理 multipart 的 limit = 1024
+                Meta::NameValue(nv) if nv.path.is_ident("limit") => {
+                    if attr_name != "multipart" {
+                        return Err(Error::new_spanned(
+                            &nv.path,
+                            "`limit` is only supported on #[multipart] fields",
+                        ));
+                    }
+                    field_format.limit = Some(meta_name_value_int(nv)?);
+                }
+                // 处理 #[body(codec = path::Codec)]
+                Meta::NameValue(nv) if nv.path.is_ident("codec") => {
+                    if attr_name != "body" {
+                        return Err(Error::new_spanned(
+                            &nv.path,
+                            "`codec` is only supported on #[body] fields",
+                        ));
+                    }
+                    let codec = &nv.value;
+                    field_format.codec = Some(quote!(#codec));
+                }
+                // 处理 #[query(style = "repeat" | "comma" | "brackets")]
+                Meta::NameValue(nv) if nv.path.is_ident("style") => {
+                    if attr_name != "query" {
+                        return Err(Error::new_spanned(
+                            &nv.path,
+                            "`style` is only supported on #[query] fields",
+                        ));
+                    }
+                    if !is_vec_type(field_type)
+                        && !get_option_inner_type(field_type).is_some_and(|ty| is_vec_type(&ty))
+                    {
+                        return Err(Error::new_spanned(
+                            field_type,
+                            "`style` needs a `Vec<T>` or `Option<Vec<T>>` field",
+                        ));
+                    }
+                    let lit_str = meta_name_value_lit(nv).ok_or_else(|| {
+                        Error::new_spanned(&nv.value, "expected a string literal")
+                    })?;
+                    let style = lit_str.value();
+                    if !QUERY_STYLES.contains(&style.as_str()) {
+                        return Err(Error::new_spanned(
+                            lit_str,
+                            format!(
+                                "unknown query style, expected one of: {}",
+                                QUERY_STYLES.join(", ")
+                            ),
+                        ));
+                    }
+                    field_format.style = Some(style);
+                }
+                // 处理 #[header(delimiter = ";")]
+                Meta::NameValue(nv) if nv.path.is_ident("delimiter") => {
+                    if !LIST_FORMATS.contains(&attr_name) {
+                        return Err(Error::new_spanned(
+                            &nv.path,
+                            "`delimiter` is only supported on #[header], #[uri] and #[cookie] fields",
+                        ));
+                    }
+                    if !composite_type(attr_name, field_type).1 {
+                        return Err(Error::new_spanned(
+                            field_type,
+                            "`delimiter` needs a collection field such as `Vec<T>`",
+                        ));
+                    }
+                    let lit_str = meta_name_value_lit(nv)
+                        .filter(|lit_str| !lit_str.value().is_empty())
+                        .ok_or_else(|| {
+                            Error::new_spanned(&nv.value, "expected a non-empty string literal")
+                        })?;
+                    field_format.delimiter = Some(lit_str.value());
+                }
+                // 处理 #[header(with = "path::parse_fn")] 或 #[uri(parse_with = path::parse_fn)]
+                Meta::NameValue(nv)
+                    if nv.path.is_ident("with") || nv.path.is_ident("parse_with") =>
+                {
+                    if !LIST_FORMATS.contains(&attr_name) {
+                        return Err(Error::new_spanned(
+                            &nv.path,
+                            "`with` is only supported on #[header], #[uri] and #[cookie] fields",
+                        ));
+                    }
+                    let parse_with = match meta_name_value_lit(nv) {
+                        Some(lit_str) => lit_str.parse::<syn::Path>()?.to_token_stream(),
+                        None => nv.value.to_token_stream(),
+                    };
+                    field_format.parse_with = Some(parse_with);
+                }
+                // 处理 #[ext(take)]，移出扩展而不是克隆
+                Meta::Path(path) if path.is_ident("take") => {
+                    if attr_name != "ext" {
+                        return Err(Error::new_spanned(
+                            path,
+                            "`take` is only supported on #[ext] fields",
+                        ));
+                    }
+                    field_format.take = true;
+                }
+                // 处理 default，缺失时使用 Default::default()
+                Meta::Path(path) if path.is_ident("default") => {
+                    check_default(path, attr_name, field_type)?;
+                    field_format.default = Some(quote!(::std::default::Default::default()));
+                }
+                // 处理 default = "expr"，缺失时使用该表达式
+                Meta::NameValue(nv) if nv.path.is_ident("default") => {
+                    check_default(&nv.path, attr_name, field_type)?;
+                    let lit_str = meta_name_value_lit(nv).ok_or_else(|| {
+                        Error::new_spanned(&nv.value, "expected a string literal")
+                    })?;
+                    let expr = lit_str.parse::<Expr>()?;
+                    field_format.default = Some(quote!(#expr));
+                }
+                _ => {
+                    return Err(Error::new_spanned(
+                        meta,
+                        format!(
+                            "unknown #[{attr_name}] option, expected one of: rename{}",
+                            match attr_name {
+                                "multipart" => ", default, limit",
+                                "body" => ", codec",
+                                "query" => ", style",
+                                "header" | "uri" | "cookie" => ", default, delimiter, with",
+                                "ext" => ", default, take",
+                                _ => "",
+                            }
+                        ),
+                    ));
+                }
+            },
+        }
+    }
+    Ok(Some(field_format))
+}
+
+/// `default` 只对先读入局部变量的非 `Option` 字段生效，其余字段由 serde 反序列化
+fn check_default(path: &syn::Path, attr_name: &str, field_type: &syn::Type) -> Result<(), Error> {
+    let is_file = attr_name == "multipart" && is_upload_file_type(field_type);
+    if !OPTION_FORMATS.contains(&attr_name) && !is_file {
+        return Err(Error::new_spanned(
+            path,
+            format!(
+                "`default` is only supported on #[header], #[uri], #[cookie], #[ext] and multipart file fields, use #[serde(default)] on #[{attr_name}] fields"
+            ),
+        ));
+    }
+    if is_option_type(field_type) {
+        return Err(Error::new_spanned(
+            path,
+            "`default` has no effect on `Option<T>` fields, which are `None` when missing",
+        ));
+    }
+    Ok(())
+}
+
+/// 源属性参数，既可以是字面量也可以是 `key = value` 等元数据
+enum AttrArg {
+    Lit(LitStr),
+    Meta(Box<Meta>),
+}
+
+impl Parse for AttrArg {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(LitStr) {
+            input.parse().map(AttrArg::Lit)
+        } else {
+            input.parse().map(|meta| AttrArg::Meta(Box::new(meta)))
+        }
+    }
+}
+
+fn validate_format(lit_str: &LitStr) -> Result<FastStr, Error> {
+    let format = FastStr::from(lit_str.value());
+    if FORMATS.contains(&format.as_str()) {
+        Ok(format)
+    } else {
+        Err(Error::new_spanned(
+            lit_str,
+            format!(
+                "Unsupported format '{}'. Expected one of: {}",
+                format,
+                FORMATS.join(", ")
+            ),
+        ))
+    }
+}
@@ -0,0 +1,182 @@
+use proc_macro2::TokenStream;
+use quote::{ToTokens, quote};
+use syn::{
+    Attribute,
+    Expr::{self},
+    Field, GenericArgument, Lit, LitStr, Meta, MetaNameValue, PathArguments, Token, Type,
+    punctuated::Punctuated,
+};
+
+pub const OPTION_FORMATS: &[&str] = &["uri", "header", "cookie", "ext"];
+
+// 按分隔符拆分多值的来源
+pub const LIST_FORMATS: &[&str] = &["uri", "header", "cookie"];
+
+// 除 `Vec` 外可以收集多值的集合
+const COLLECT_TYPES: &[&str] = &["HashSet", "BTreeSet", "SmallVec"];
+
+/// 多值字段的目标集合，解析时统一先收集到 `Vec` 再转换
+#[derive(Clone)]
+pub enum Collection {
+    /// `HashSet`、`BTreeSet`、`SmallVec` 等，经 `FromIterator` 转换
+    Collect(TokenStream),
+    /// `[T; N]`，值的个数必须为 N
+    Array(TokenStream),
+}
+
+pub fn outer_type(symbol: &str, ty: &Type) -> bool {
+    match ty {
+        Type::Path(type_path) => {
+            let path = &type_path.path;
+            path.is_ident(symbol) || path.segments.iter().any(|segment| segment.ident == symbol)
+        }
+        _ => false,
+    }
+}
+
+pub fn is_option_type(ty: &Type) -> bool {
+    outer_type("Option", ty)
+}
+
+pub fn is_vec_type(ty: &Type) -> bool {
+    outer_type("Vec", ty)
+}
+
+pub fn get_inner_type(symbol: &str, ty: &Type) -> Option<Type> {
+    if let Type::Path(type_path) = ty
+        && let Some(segment) = type_path.path.segments.last()
+        && segment.ident == symbol
+        && let PathArguments::AngleBracketed(args) = &segment.arguments
+        && let Some(GenericArgument::Type(inner_ty)) = args.args.first()
+    {
+        return Some(inner_ty.to_owned());
+    }
+    None
+}
+
+pub fn get_option_inner_type(ty: &Type) -> Option<Type> {
+    get_inner_type("Option", ty)
+}
+
+pub fn get_vec_inner_type(ty: &Type) -> Option<Type> {
+    get_inner_type("Vec", ty)
+}
+
+/// 拆出 `HashSet<T>`、`BTreeSet<T>`、`SmallVec<[T; N]>` 与 `[T; N]` 的元素类型
+pub fn get_collection_inner_type(ty: &Type) -> Option<(Type, Collection)> {
+    if let Type::Array(array) = ty {
+        let len = &array.len;
+        return Some(((*array.elem).clone(), Collection::Array(quote!(#len))));
+    }
+    COLLECT_TYPES.iter().find_map(|symbol| {
+        let inner = match get_inner_type(symbol, ty)? {
+            // SmallVec 的类型参数是数组
+            Type::Array(array) if *symbol == "SmallVec" => *array.elem,
+            inner => inner,
+        };
+        Some((inner, Collection::Collect(ty.to_token_stream())))
+    })
+}
+
+/// header/uri/cookie 字段除 `Vec` 外的多值集合
+pub fn collection_type(format: &str, ty: &Type) -> Option<Collection> {
+    if !LIST_FORMATS.contains(&format) {
+        return None;
+    }
+    let ty = get_option_inner_type(ty).unwrap_or_else(|| ty.clone());
+    get_collection_inner_type(&ty).map(|(_, collection)| collection)
+}
+
+/// multipart 中 `UploadFile`、`Option<UploadFile>`、`Vec<UploadFile>` 字段按文件绑定
+pub fn is_upload_file_type(ty: &Type) -> bool {
+    let ty = get_option_inner_type(ty).unwrap_or_else(|| ty.clone());
+    let ty = get_vec_inner_type(&ty).unwrap_or(ty);
+    outer_type("UploadFile", &ty)
+}
+
+pub fn composite_type(format: &str, ty: &Type) -> (bool, bool, TokenStream) {
+    // protobuf 整个请求体、ext 整个扩展对应一个字段，只拆 Option
+    if format == "protobuf" || format == "ext" {
+        if let Some(cty) = get_option_inner_type(ty) {
+            return (true, false, cty.to_token_stream());
+        }
+        return (false, false, ty.to_token_stream());
+    }
+    if OPTION_FORMATS.contains(&format) || (format == "multipart" && is_upload_file_type(ty)) {
+        let is_option = is_option_type(ty);
+        let cty = get_option_inner_type(ty).unwrap_or_else(|| ty.clone());
+        if let Some(inner) = get_vec_inner_type(&cty) {
+            return (is_option, true, inner.to_token_stream());
+        }
+        if LIST_FORMATS.contains(&format)
+            && let Some((inner, _)) = get_collection_inner_type(&cty)
+        {
+            return (is_option, true, inner.to_token_stream());
+        }
+        if is_option {
+            return (is_option, false, cty.to_token_stream());
+        }
+    }
+
+    (false, false, ty.to_token_stream())
+}
+
+/// 合并 `///` 文档注释，没有注释时为 `None`
+pub fn doc_comment(attrs: &[Attribute]) -> Option<String> {
+    let lines: Vec<_> = attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            Meta::NameValue(nv) => meta_name_value_str(nv),
+            _ => None,
+        })
+        .map(|line| line.trim().to_string())
+        .collect();
+    let doc = lines.join("\n").trim().to_string();
+    (!doc.is_empty()).then_some(doc)
+}
+
+pub fn serde_indent(field: &Field) -> (Option<TokenStream>, Option<String>) {
+    let serde_indent = field
+        .attrs
+        .iter()
+        .rfind(|attr| attr.path().is_ident("serde"));
+
+    let serde_attr = serde_indent.map(|m| m.to_token_stream());
+    let rename = serde_indent.and_then(|m| {
+        m.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)
+            .ok()
+            .and_then(|nested| {
+                nested.into_iter().find_map(|meta| match &meta {
+                    Meta::NameValue(nv) if nv.path.is_ident("rename") => meta_name_value_str(nv),
+                    _ => None,
+                })
+            })
+    });
+    (serde_attr, rename)
+}
+
+pub fn meta_name_value_lit(nv: &MetaNameValue) -> Option<&LitStr> {
+    if let Expr::Lit(expr_lit) = &nv.value
+        && let Lit::Str(lit_str) = &expr_lit.lit
+    {
+        return Some(lit_str);
+    }
+    None
+}
+
+pub fn meta_name_value_int(nv: &MetaNameValue) -> Result<u64, syn::Error> {
+    if let Expr::Lit(expr_lit) = &nv.value
+        && let Lit::Int(lit_int) = &expr_lit.lit
+    {
+        return lit_int.base10_parse();
+    }
+    Err(syn::Error::new_spanned(
+        &nv.value,
+        "expected an integer literal",
+    ))
+}
+
+pub fn meta_name_value_str(nv: &MetaNameValue) -> Option<String> {
+    meta_name_value_lit(nv).map(LitStr::value)
+}
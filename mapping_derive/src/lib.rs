@@ -0,0 +1,34 @@
+use proc_macro::TokenStream;
+use syn::{DeriveInput, parse_macro_input};
+mod client;
+mod deserialize;
+mod field_attr;
+mod helper;
+mod mapping;
+mod openapi;
+mod response;
+
+#[proc_macro_derive(
+    Mapping,
+    attributes(
+        mapping, format, ext, json, form, msgpack, cbor, xml, protobuf, body, multipart, uri,
+        header, query, cookie, serde, validate
+    )
+)]
+pub fn param_bind_derive(input: TokenStream) -> TokenStream {
+    let mut input = parse_macro_input!(input as DeriveInput);
+    mapping::expand_params_mapping(&mut input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[proc_macro_derive(
+    IntoResponseMapping,
+    attributes(mapping, format, status, json, form, header, cookie, serde)
+)]
+pub fn into_response_derive(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    response::expand_into_response(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
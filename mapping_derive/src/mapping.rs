@@ -0,0 +1,727 @@
+use super::client::into_request_expanded;
+use super::deserialize::*;
+use super::field_attr::*;
+use super::helper::{OPTION_FORMATS, doc_comment};
+use super::openapi::openapi_expanded;
+use faststr::FastStr;
+use linked_hash_map::LinkedHashMap;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use std::collections::HashMap;
+use syn::{
+    DeriveInput, Error, Field, Fields, Generics, Token, WherePredicate, parse_quote,
+    punctuated::Punctuated,
+};
+
+pub fn expand_params_mapping(input: &mut DeriveInput) -> Result<TokenStream, Error> {
+    let struct_name = &input.ident;
+    let default_format = get_default_format(&input.attrs)?;
+    let options = get_mapping_options(&input.attrs, &MappingOptions::default())?;
+    if options.flatten
+        || options.from.is_some()
+        || options.try_from.is_some()
+        || options.rename.is_some()
+    {
+        return Err(Error::new_spanned(
+            struct_name,
+            "`flatten`, `from` and `try_from` are only allowed on fields and `rename` on enum variants",
+        ));
+    }
+
+    // 结构体对应一组字段，枚举的每个变体各对应一组字段
+    let mut groups = Vec::new();
+    match &input.data {
+        syn::Data::Struct(data) => {
+            if options.tag.is_some() {
+                return Err(Error::new_spanned(
+                    struct_name,
+                    "`tag` is only allowed on enums",
+                ));
+            }
+            let Fields::Named(fields_named) = &data.fields else {
+                return Err(Error::new_spanned(
+                    struct_name,
+                    "Mapping can only be derived for structs with named fields",
+                ));
+            };
+            groups.push(FieldGroup::new(
+                quote! { Self },
+                default_format.as_str(),
+                &options,
+                fields_named.named.iter(),
+            )?);
+        }
+        syn::Data::Enum(data) => {
+            if options.client || options.path.is_some() {
+                return Err(Error::new_spanned(
+                    struct_name,
+                    "`client` and `path` are only allowed on structs",
+                ));
+            }
+            if options.tag.is_none() {
+                return Err(Error::new_spanned(
+                    struct_name,
+                    "enums need a discriminator such as #[mapping(tag = header(\"x-api-version\"))]",
+                ));
+            }
+            for variant in &data.variants {
+                let variant_format = match get_default_format(&variant.attrs)? {
+                    format
+                        if variant
+                            .attrs
+                            .iter()
+                            .any(|attr| attr.path().is_ident("format")) =>
+                    {
+                        format
+                    }
+                    _ => default_format.clone(),
+                };
+                let variant_options = get_mapping_options(&variant.attrs, &options)?;
+                if variant_options.client || variant_options.path != options.path {
+                    return Err(Error::new_spanned(
+                        &variant.ident,
+                        "`client` and `path` are only allowed on structs",
+                    ));
+                }
+                if variant_options.accept != options.accept
+                    || variant_options.tag != options.tag
+                    || variant_options.flatten
+                    || variant_options.from.is_some()
+                    || variant_options.try_from.is_some()
+                {
+                    return Err(Error::new_spanned(
+                        &variant.ident,
+                        "`accept`, `tag`, `flatten`, `from` and `try_from` are not allowed on variants",
+                    ));
+                }
+                let fields = match &variant.fields {
+                    Fields::Named(fields_named) => fields_named.named.iter().collect(),
+                    Fields::Unit => Vec::new(),
+                    Fields::Unnamed(_) => {
+                        return Err(Error::new_spanned(
+                            &variant.ident,
+                            "Mapping variants must have named fields",
+                        ));
+                    }
+                };
+                let variant_ident = &variant.ident;
+                let mut group = FieldGroup::new(
+                    quote! { Self::#variant_ident },
+                    variant_format.as_str(),
+                    &variant_options,
+                    fields.into_iter(),
+                )?;
+                group.tag = variant_options
+                    .rename
+                    .clone()
+                    .unwrap_or_else(|| variant_ident.to_string());
+                groups.push(group);
+            }
+        }
+        syn::Data::Union(_) => {
+            return Err(Error::new_spanned(
+                struct_name,
+                "Mapping can only be derived for structs and enums",
+            ));
+        }
+    }
+
+    let field_infos: Vec<FieldInfo> = groups
+        .iter()
+        .flat_map(|group| group.field_infos.iter().cloned())
+        .collect();
+    check_body_fields(input, &field_infos, options.accept.as_deref())?;
+    if let Some(path) = &options.path {
+        check_path_fields(input, path, &field_infos)?;
+    }
+    for group in &groups {
+        check_protobuf_fields(&group.field_infos)?;
+    }
+    let generics = generic_bounds(&input.generics, &field_infos);
+
+    // 枚举的分派字段按普通字段的方式读取
+    let tag_group = options.tag.as_ref().map(|tag| tag_group(tag, &options));
+
+    // 未声明 accept 时接受结构体中出现的请求体格式
+    let accept = options.accept.clone().unwrap_or_else(|| {
+        BODY_FORMATS
+            .iter()
+            .filter(|format| {
+                groups
+                    .iter()
+                    .chain(tag_group.as_ref())
+                    .any(|group| group.sorted_fields.contains_key(**format))
+            })
+            .map(|format| format.to_string())
+            .collect()
+    });
+    let fields_of = |format: &str| -> Option<Vec<FieldInfo>> {
+        let fields: Vec<_> = field_infos
+            .iter()
+            .filter(|field| field.format == format)
+            .cloned()
+            .collect();
+        (!fields.is_empty()).then_some(fields)
+    };
+    let body_expanded = body_expanded(
+        &accept,
+        fields_of("body").as_ref(),
+        fields_of("multipart").as_ref(),
+        &options,
+    );
+
+    let extract_expanded = match &tag_group {
+        None => {
+            let group_expanded = groups[0].extract_expanded(&generics);
+            quote! {
+                #body_expanded
+                #group_expanded
+            }
+        }
+        Some(tag_group) => {
+            let tag_expanded = tag_group.bind_expanded(&generics);
+            let tag_value = field_value_expanded(&tag_group.field_infos[0]);
+            let tag = options.tag.as_ref().unwrap();
+            let source = source_expanded(&tag.format);
+            let tag_name = &tag.name;
+            let tags: Vec<_> = groups.iter().map(|group| group.tag.clone()).collect();
+            let arms = groups.iter().map(|group| {
+                let tag = &group.tag;
+                let group_expanded = group.extract_expanded(&generics);
+                quote! {
+                    Some(#tag) => {
+                        #group_expanded
+                    }
+                }
+            });
+            quote! {
+                #body_expanded
+                let __tag = {
+                    #tag_expanded
+                    #tag_value
+                };
+                match __tag.as_deref() {
+                    #(#arms)*
+                    _ => Err(::mapping::MappingRejection::unknown_tag(
+                        #source,
+                        #tag_name,
+                        __tag,
+                        &[#(#tags),*],
+                    )),
+                }
+            }
+        }
+    };
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    // 不读取请求体的结构体同时实现 FromParts，可以被 flatten 到其他结构体中
+    let expanded = if accept.is_empty() {
+        quote! {
+            impl #impl_generics ::mapping::FromParts for #struct_name #ty_generics #where_clause {
+                async fn from_parts(
+                    cx: &mut volo_http::context::ServerContext,
+                    parts: &volo_http::http::request::Parts,
+                ) -> Result<Self, ::mapping::MappingRejection> {
+                    #extract_expanded
+                }
+            }
+            impl #impl_generics volo_http::server::extract::FromRequest
+                for #struct_name #ty_generics #where_clause
+            {
+                type Rejection = ::mapping::MappingRejection;
+
+                async fn from_request(
+                    cx: &mut volo_http::context::ServerContext,
+                    parts: volo_http::http::request::Parts,
+                    _body: volo_http::body::Body,
+                ) -> Result<Self, Self::Rejection> {
+                    <Self as ::mapping::FromParts>::from_parts(cx, &parts).await
+                }
+            }
+        }
+    } else {
+        quote! {
+            impl #impl_generics volo_http::server::extract::FromRequest
+                for #struct_name #ty_generics #where_clause
+            {
+                type Rejection = ::mapping::MappingRejection;
+
+                async fn from_request(
+                    cx: &mut volo_http::context::ServerContext,
+                    parts: volo_http::http::request::Parts,
+                    body: volo_http::body::Body,
+                ) -> Result<Self, Self::Rejection> {
+                    #extract_expanded
+                }
+            }
+        }
+    };
+
+    // 客户端的写入只依赖字段本身，约束单独计算
+    let client_expanded = if options.client {
+        into_request_expanded(struct_name, &input.generics, &field_infos)?
+    } else {
+        quote! {}
+    };
+
+    let openapi_expanded = openapi_expanded(
+        struct_name,
+        &input.generics,
+        &groups
+            .iter()
+            .map(|group| group.field_infos.as_slice())
+            .collect::<Vec<_>>(),
+        options
+            .tag
+            .as_ref()
+            .map(|tag| (tag, groups.iter().map(|group| group.tag.clone()).collect())),
+        doc_comment(&input.attrs),
+    );
+
+    let path_expanded = options.path.as_ref().map(|path| {
+        let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+        quote! {
+            impl #impl_generics #struct_name #ty_generics #where_clause {
+                pub const PATH: &'static str = #path;
+            }
+        }
+    });
+
+    Ok(quote! {
+        #expanded
+        #client_expanded
+        #openapi_expanded
+        #path_expanded
+    })
+}
+
+/// 一组具名字段：结构体本身，或枚举的一个变体
+struct FieldGroup {
+    /// 构造值的路径，`Self` 或 `Self::Variant`
+    constructor: TokenStream,
+    /// 枚举变体的分派值
+    tag: String,
+    field_infos: Vec<FieldInfo>,
+    sorted_fields: LinkedHashMap<FastStr, Vec<FieldInfo>>,
+}
+
+impl FieldGroup {
+    fn new<'a>(
+        constructor: TokenStream,
+        default_format: &str,
+        options: &MappingOptions,
+        fields: impl Iterator<Item = &'a Field>,
+    ) -> Result<Self, Error> {
+        let mut field_infos = Vec::new();
+        for field in fields {
+            let mut field_info = get_field_info(default_format, options, field)?;
+            field_info.doc = doc_comment(&field.attrs);
+            field_infos.push(field_info);
+        }
+        Ok(Self::from_field_infos(constructor, field_infos))
+    }
+
+    fn from_field_infos(constructor: TokenStream, field_infos: Vec<FieldInfo>) -> Self {
+        let mut map_fields: HashMap<FastStr, Vec<FieldInfo>> = HashMap::new();
+        for field in &field_infos {
+            map_fields
+                .entry(FastStr::new(field.format.as_str()))
+                .or_default()
+                .push(field.clone());
+        }
+        let mut sorted_fields: LinkedHashMap<FastStr, Vec<FieldInfo>> = LinkedHashMap::new();
+        for format in FORMATS {
+            let format = *format;
+            if let Some(val) = map_fields.get(format) {
+                sorted_fields.insert(FastStr::new(format), val.to_owned());
+            }
+        }
+        Self {
+            constructor,
+            tag: String::new(),
+            field_infos,
+            sorted_fields,
+        }
+    }
+
+    /// 读取各来源到局部变量或 `XxxMode`，请求体已由调用方读入 `request_body`
+    fn bind_expanded(&self, generics: &Generics) -> TokenStream {
+        let field_infos = &self.field_infos;
+        let format_deserialize_expanded = self
+            .sorted_fields
+            .iter()
+            .map(|(format, items)| format_expanded(format, items, generics));
+        let local_definitions = field_infos
+            .iter()
+            .filter(|field| field.is_local())
+            .map(local_declare_expanded);
+        let flatten_expanded = field_infos
+            .iter()
+            .filter(|field| field.format == "flatten")
+            .map(flatten_expanded);
+        let fallback_expanded = field_infos
+            .iter()
+            .filter(|field| field.format == "from")
+            .map(fallback_expanded);
+        quote! {
+            #(#local_definitions)*
+            #(#flatten_expanded)*
+            #(#format_deserialize_expanded)*
+            #(#fallback_expanded)*
+        }
+    }
+
+    /// 读取各来源并构造值
+    fn extract_expanded(&self, generics: &Generics) -> TokenStream {
+        let constructor = &self.constructor;
+        let field_infos = &self.field_infos;
+        let bind_expanded = self.bind_expanded(generics);
+        let field_idents = field_infos
+            .iter()
+            .map(|field| format_ident!("{}", field.name));
+        let field_values = field_infos.iter().map(field_value_expanded);
+
+        // 所有字段绑定完成后统一校验，通过后再构造值
+        let validate_expanded: Vec<_> = field_infos
+            .iter()
+            .filter(|field| !field.validators.is_empty())
+            .map(validate_expanded)
+            .collect();
+        let validate_expanded = (!validate_expanded.is_empty()).then(|| {
+            quote! {
+                let mut __errors = Vec::new();
+                #(#validate_expanded)*
+                if !__errors.is_empty() {
+                    return Err(::mapping::MappingRejection::Validation(__errors));
+                }
+            }
+        });
+        let value_idents: Vec<_> = field_infos.iter().map(value_ident).collect();
+        let construct_expanded = quote! {
+            #(let #value_idents = #field_values;)*
+            #validate_expanded
+            Ok(#constructor {
+                #(#field_idents: #value_idents,)*
+            })
+        };
+
+        quote! {
+            #bind_expanded
+            #construct_expanded
+        }
+    }
+}
+
+/// 枚举分派字段当作一个 `Option<String>` 字段读取，块的值即为分派值
+fn tag_group(tag: &TagInfo, options: &MappingOptions) -> FieldGroup {
+    let rename = &tag.name;
+    let is_local = OPTION_FORMATS.contains(&tag.format.as_str());
+    let field = FieldInfo {
+        name: "tag".to_string(),
+        f_type: if is_local {
+            quote! { String }
+        } else {
+            quote! { Option<String> }
+        },
+        format: tag.format.clone(),
+        serde: (!is_local).then(|| quote! { #[serde(default, rename = #rename)] }),
+        rename: tag.name.clone(),
+        is_option: true,
+        lenient: options.lenient,
+        ..Default::default()
+    };
+    FieldGroup::from_field_infos(quote! {}, vec![field])
+}
+
+/// 字段类型用到泛型参数时，按字段来源补充生成代码需要的约束
+fn generic_bounds(generics: &Generics, field_infos: &[FieldInfo]) -> Generics {
+    let mut generics = generics.clone();
+    let type_params: Vec<_> = generics
+        .type_params()
+        .map(|param| param.ident.to_string())
+        .collect();
+    if type_params.is_empty() {
+        return generics;
+    }
+    let where_clause = generics.make_where_clause();
+    for field in field_infos {
+        let fty = &field.f_type;
+        let raw = field.try_from.as_ref();
+        if !uses_type_params(quote!(#fty #raw), &type_params) {
+            continue;
+        }
+        // `try_from` 字段先读取为原始类型
+        let decoded = raw.unwrap_or(fty);
+        if let Some(raw) = raw {
+            let predicates: Punctuated<WherePredicate, Token![,]> = parse_quote! {
+                #fty: ::std::convert::TryFrom<#raw> + Send,
+                <#fty as ::std::convert::TryFrom<#raw>>::Error: Into<volo_http::error::BoxError>
+            };
+            where_clause.predicates.extend(predicates);
+        }
+        let predicates = match field.format.as_str() {
+            // `with` 指定的解析函数自带约束
+            "header" | "uri" | "cookie" if field.parse_with.is_some() => continue,
+            "header" | "uri" | "cookie" | "from" if field.lenient => quote! {
+                #decoded: ::std::str::FromStr + ::std::default::Default + Send
+            },
+            "header" | "uri" | "cookie" | "from" => quote! {
+                #decoded: ::std::str::FromStr + Send,
+                <#decoded as ::std::str::FromStr>::Err: Into<volo_http::error::BoxError>
+            },
+            "ext" if field.take => quote! { #fty: Send + Sync + 'static },
+            "ext" => quote! { #fty: Clone + Send + Sync + 'static },
+            "flatten" => quote! { #fty: ::mapping::FromParts + Send },
+            // protobuf 与 multipart 文件字段的类型由运行时函数约束
+            "protobuf" => continue,
+            _ if field.is_file => continue,
+            _ => quote! { #decoded: serde::de::DeserializeOwned + Send },
+        };
+        let predicates: Punctuated<WherePredicate, Token![,]> = parse_quote!(#predicates);
+        where_clause.predicates.extend(predicates);
+    }
+    generics
+}
+
+pub fn uses_type_params(tokens: TokenStream, type_params: &[String]) -> bool {
+    tokens.into_iter().any(|token| match token {
+        proc_macro2::TokenTree::Ident(ident) => type_params.contains(&ident.to_string()),
+        proc_macro2::TokenTree::Group(group) => uses_type_params(group.stream(), type_params),
+        _ => false,
+    })
+}
+
+/// `#[mapping(path = "...")]` 的每个占位符都要有对应的 `#[uri]` 字段，反之亦然
+fn check_path_fields(
+    input: &DeriveInput,
+    path: &str,
+    field_infos: &[FieldInfo],
+) -> Result<(), Error> {
+    let placeholders = path_placeholders(path).map_err(|message| {
+        Error::new_spanned(&input.ident, format!("invalid path `{path}`: {message}"))
+    })?;
+    let mut bound = Vec::new();
+    for field in field_infos {
+        let names: Vec<&str> = match field.format.as_str() {
+            "uri" => vec![field.rename.as_str()],
+            "from" => field
+                .from
+                .iter()
+                .filter(|(format, _)| format == "uri")
+                .map(|(_, name)| name.as_str())
+                .collect(),
+            _ => continue,
+        };
+        for name in names {
+            if !placeholders.contains(&name) {
+                return Err(Error::new_spanned(
+                    &field.f_type,
+                    format!(
+                        "`{}` reads `{name}`, which is not a placeholder in `{path}`",
+                        field.name
+                    ),
+                ));
+            }
+            bound.push(name);
+        }
+    }
+    // flatten 字段的 `#[uri]` 字段在展开时不可见
+    if field_infos.iter().any(|field| field.format == "flatten") {
+        return Ok(());
+    }
+    if let Some(name) = placeholders.iter().find(|name| !bound.contains(name)) {
+        return Err(Error::new_spanned(
+            &input.ident,
+            format!("placeholder `{{{name}}}` in `{path}` has no #[uri] field"),
+        ));
+    }
+    Ok(())
+}
+
+/// 解析路由模板中的 `{name}` 与 `{*name}` 占位符
+fn path_placeholders(path: &str) -> Result<Vec<&str>, String> {
+    if !path.starts_with('/') {
+        return Err("paths must start with `/`".to_string());
+    }
+    let mut placeholders = Vec::new();
+    let mut rest = path;
+    while let Some(start) = rest.find(['{', '}']) {
+        if rest[start..].starts_with('}') {
+            return Err("unmatched `}`".to_string());
+        }
+        let Some(len) = rest[start + 1..].find('}') else {
+            return Err("unmatched `{`".to_string());
+        };
+        let inner = &rest[start + 1..start + 1 + len];
+        let name = inner.strip_prefix('*').unwrap_or(inner);
+        if name.is_empty() || name.contains('{') {
+            return Err(format!("invalid placeholder `{{{inner}}}`"));
+        }
+        if placeholders.contains(&name) {
+            return Err(format!("duplicate placeholder `{{{inner}}}`"));
+        }
+        placeholders.push(name);
+        rest = &rest[start + len + 2..];
+    }
+    Ok(placeholders)
+}
+
+/// `#[body]` 字段必须指定同一个 codec
+fn check_body_fields(
+    input: &DeriveInput,
+    field_infos: &[FieldInfo],
+    accept: Option<&[String]>,
+) -> Result<(), Error> {
+    let mut codec: Option<String> = None;
+    for field in field_infos.iter().filter(|field| field.format == "body") {
+        let Some(field_codec) = &field.codec else {
+            return Err(Error::new_spanned(
+                &field.f_type,
+                format!("`{}` needs #[body(codec = path::to::Codec)]", field.name),
+            ));
+        };
+        let field_codec = field_codec.to_string();
+        match &codec {
+            Some(codec) if *codec != field_codec => {
+                return Err(Error::new_spanned(
+                    &field.f_type,
+                    format!(
+                        "`{}` uses a different codec than the other #[body] fields",
+                        field.name
+                    ),
+                ));
+            }
+            _ => codec = Some(field_codec),
+        }
+    }
+    if codec.is_none() && accept.is_some_and(|accept| accept.iter().any(|format| format == "body"))
+    {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "`accept = [\"body\"]` needs a #[body(codec = ...)] field",
+        ));
+    }
+    Ok(())
+}
+
+/// `#[protobuf]` 字段接收整个请求体，每组字段至多一个
+fn check_protobuf_fields(field_infos: &[FieldInfo]) -> Result<(), Error> {
+    if let Some(field) = field_infos
+        .iter()
+        .filter(|field| field.format == "protobuf")
+        .nth(1)
+    {
+        return Err(Error::new_spanned(
+            &field.f_type,
+            "only one #[protobuf] field is allowed, it receives the whole body",
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse_quote;
+
+    #[test]
+    fn test_expand_params_mapping() {
+        let mut input: DeriveInput = parse_quote! {
+            #[derive(Mapping, Default, Debug)]
+            #[format = "json"]
+            pub struct TestParam{
+                #[header]
+                #[serde(default, rename = "token")]
+                 token: Option<i64>,
+                 #[header]
+                 ids: Vec<i64>,
+                 #[json]
+                #[serde(default)]
+                 name: i64,
+                 #[ext]
+                 user_id: i64,
+                 #[query]
+                #[serde(default)]
+                 id: i64,
+                #[form]
+                #[serde(default)]
+                 uid: i64,
+                 #[uri]
+                 pid: Option<i64>,
+                 #[uri]
+                 cid: String,
+                 #[uri]
+                 cids: Vec<i64>,
+                 #[uri]
+                 items: Option<Vec<i64>>,
+            }
+        };
+        let result = expand_params_mapping(&mut input).unwrap();
+        println!("code: \n{}", result);
+        assert!(result.to_string().contains("MappingRejection"));
+    }
+
+    #[test]
+    fn test_expand_lenient() {
+        let mut input: DeriveInput = parse_quote! {
+            #[derive(Mapping, Default, Debug)]
+            pub struct TestParam{
+                #[header]
+                ids: Vec<i64>,
+                #[header]
+                #[mapping(lenient)]
+                lenient_ids: Vec<i64>,
+            }
+        };
+        let result = expand_params_mapping(&mut input).unwrap().to_string();
+        assert!(result.contains("invalid_value"));
+        assert!(result.contains("unwrap_or_default"));
+
+        let mut input: DeriveInput = parse_quote! {
+            #[derive(Mapping, Default, Debug)]
+            #[mapping(lenient)]
+            pub struct TestParam{
+                #[header]
+                ids: Vec<i64>,
+            }
+        };
+        let result = expand_params_mapping(&mut input).unwrap().to_string();
+        assert!(!result.contains("invalid_value"));
+    }
+
+    #[test]
+    fn test_reject_unsupported_shapes() {
+        let inputs: Vec<DeriveInput> = vec![
+            parse_quote! { struct TupleParam(#[header] i64); },
+            parse_quote! { struct UnitParam; },
+            parse_quote! { enum Untagged { A { #[header] id: i64 } } },
+            parse_quote! {
+                #[mapping(tag = header("x-api-version"))]
+                enum TupleVariant { A(i64) }
+            },
+            parse_quote! { union Raw { id: i64 } },
+        ];
+        for mut input in inputs {
+            assert!(expand_params_mapping(&mut input).is_err());
+        }
+    }
+
+    #[test]
+    fn test_path_placeholders() {
+        assert_eq!(
+            path_placeholders("/test/{pid}/{cid}/{*rest}").unwrap(),
+            ["pid", "cid", "rest"]
+        );
+        assert!(path_placeholders("/plain").unwrap().is_empty());
+        for path in [
+            "test/{pid}",
+            "/test/{pid",
+            "/test/pid}",
+            "/test/{}",
+            "/{a}/{*a}",
+        ] {
+            assert!(path_placeholders(path).is_err(), "{path}");
+        }
+    }
+}
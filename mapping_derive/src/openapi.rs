@@ -0,0 +1,386 @@
+use super::deserialize::codec_path;
+use super::field_attr::*;
+use super::helper::is_option_type;
+use proc_macro2::TokenStream;
+use quote::{ToTokens, quote};
+use syn::{
+    Attribute, GenericArgument, Generics, Ident, Meta, PathArguments, Token, Type, WherePredicate,
+    parse::Parser, parse_quote, punctuated::Punctuated,
+};
+
+/// 生成 `OpenApiSchema`：各字段按来源写成参数或请求体属性
+///
+/// 枚举各变体的字段合并描述，除分派字段外都不是必填。
+pub fn openapi_expanded(
+    struct_name: &Ident,
+    generics: &Generics,
+    groups: &[&[FieldInfo]],
+    tag: Option<(&TagInfo, Vec<String>)>,
+    description: Option<String>,
+) -> TokenStream {
+    let is_enum = tag.is_some();
+    let fields: Vec<&FieldInfo> = groups.iter().flat_map(|fields| fields.iter()).collect();
+
+    let mut parameters: Vec<TokenStream> = Vec::new();
+    if let Some((tag, tags)) = &tag
+        && let Some(location) = parameter_in(&tag.format)
+    {
+        let name = &tag.name;
+        parameters.push(quote! {
+            parameters.push(::mapping::openapi::Parameter {
+                name: #name.to_owned(),
+                location: #location,
+                required: true,
+                description: None,
+                explode: None,
+                schema: ::mapping::openapi::Schema::one_of(&[#(#tags),*]),
+            });
+        });
+    }
+    for field in &fields {
+        match field.format.as_str() {
+            "flatten" => {
+                let fty = &field.f_type;
+                parameters.push(quote! {
+                    parameters.extend(<#fty as ::mapping::OpenApiSchema>::openapi_parameters());
+                });
+            }
+            // `from` 字段的每个来源都可以单独提供值
+            "from" => {
+                let schema = field_schema(field);
+                let description = description_expanded(field);
+                for (format, name) in &field.from {
+                    let location = parameter_in(format).unwrap();
+                    parameters.push(quote! {
+                        parameters.push(::mapping::openapi::Parameter {
+                            name: #name.to_owned(),
+                            location: #location,
+                            required: false,
+                            description: #description,
+                            explode: None,
+                            schema: #schema,
+                        });
+                    });
+                }
+            }
+            format => {
+                let Some(location) = parameter_in(format) else {
+                    continue;
+                };
+                let name = &field.rename;
+                let required = !is_enum && is_required(field);
+                let schema = field_schema(field);
+                let description = description_expanded(field);
+                let explode = match field.style.as_deref() {
+                    Some("comma") => quote! { Some(false) },
+                    _ => quote! { None },
+                };
+                parameters.push(quote! {
+                    parameters.push(::mapping::openapi::Parameter {
+                        name: #name.to_owned(),
+                        location: #location,
+                        required: #required,
+                        description: #description,
+                        explode: #explode,
+                        schema: #schema,
+                    });
+                });
+            }
+        }
+    }
+
+    // 每种请求体格式对应一个媒体类型，属性为该格式的字段
+    let mut content = Vec::new();
+    let mut body_required = false;
+    for format in BODY_FORMATS {
+        let body_fields: Vec<&FieldInfo> = fields
+            .iter()
+            .copied()
+            .filter(|field| field.format == *format)
+            .collect();
+        let tag_property = tag.as_ref().filter(|(tag, _)| tag.format == *format);
+        if body_fields.is_empty() && tag_property.is_none() {
+            continue;
+        }
+        let media_type = media_type(format, &body_fields);
+        let schema = if *format == "protobuf" {
+            body_required |= body_fields.iter().any(|field| !field.is_option);
+            quote! { ::mapping::openapi::Schema::default() }
+        } else {
+            let tag_property = tag_property.map(|(tag, tags)| {
+                let name = &tag.name;
+                quote! { .property(#name, ::mapping::openapi::Schema::one_of(&[#(#tags),*]), true) }
+            });
+            let properties = body_fields.iter().map(|field| {
+                let name = &field.rename;
+                let required = !is_enum && is_required(field);
+                body_required |= required;
+                let schema = field_schema(field);
+                // 请求体属性没有单独的描述字段，写在 schema 上
+                let description = field
+                    .doc
+                    .as_ref()
+                    .map(|doc| quote! { .description(Some(#doc)) });
+                quote! { .property(#name, #schema #description, #required) }
+            });
+            quote! {
+                ::mapping::openapi::Schema::typed("object", None)
+                    #tag_property
+                    #(#properties)*
+            }
+        };
+        content.push(quote! {
+            (#media_type, ::mapping::openapi::MediaType { schema: #schema })
+        });
+    }
+    let request_body = if content.is_empty() {
+        quote! { None }
+    } else {
+        quote! {
+            Some(::mapping::openapi::RequestBody {
+                required: #body_required,
+                content: vec![#(#content),*],
+            })
+        }
+    };
+    let description = description.map(|description| {
+        quote! {
+            fn openapi_description() -> Option<&'static str> {
+                Some(#description)
+            }
+        }
+    });
+
+    let generics = openapi_bounds(generics, &fields);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    quote! {
+        impl #impl_generics ::mapping::OpenApiSchema for #struct_name #ty_generics #where_clause {
+            fn openapi_parameters() -> Vec<::mapping::openapi::Parameter> {
+                #[allow(unused_mut)]
+                let mut parameters = Vec::new();
+                #(#parameters)*
+                parameters
+            }
+
+            fn openapi_request_body() -> Option<::mapping::openapi::RequestBody> {
+                #request_body
+            }
+
+            #description
+        }
+    }
+}
+
+fn parameter_in(format: &str) -> Option<TokenStream> {
+    let location = match format {
+        "header" => quote! { Header },
+        "uri" => quote! { Path },
+        "query" => quote! { Query },
+        "cookie" => quote! { Cookie },
+        _ => return None,
+    };
+    Some(quote! { ::mapping::openapi::ParameterIn::#location })
+}
+
+fn media_type(format: &str, fields: &[&FieldInfo]) -> TokenStream {
+    match format {
+        "json" => quote! { "application/json" },
+        "form" => quote! { "application/x-www-form-urlencoded" },
+        "multipart" => quote! { "multipart/form-data" },
+        "protobuf" => quote! { "application/x-protobuf" },
+        _ => {
+            let fields: Vec<FieldInfo> = fields.iter().map(|field| (*field).clone()).collect();
+            let codec = codec_path(format, &fields);
+            quote! {
+                <#codec as ::mapping::codec::BodyCodec>::MEDIA_TYPES
+                    .first()
+                    .copied()
+                    .unwrap_or("application/octet-stream")
+            }
+        }
+    }
+}
+
+fn description_expanded(field: &FieldInfo) -> TokenStream {
+    match &field.doc {
+        Some(doc) => quote! { Some(#doc.to_owned()) },
+        None => quote! { None },
+    }
+}
+
+/// 缺失时是否拒绝请求；路径参数总是必填
+fn is_required(field: &FieldInfo) -> bool {
+    if field.default.is_some() {
+        return false;
+    }
+    if field.format == "uri" {
+        return true;
+    }
+    if field.is_local() {
+        return !field.is_option;
+    }
+    // serde 来源的字段按类型与 `#[serde(default)]` 判断
+    let serde_default = field.serde.as_ref().is_some_and(has_serde_default);
+    let is_option = field.is_option
+        || syn::parse2::<Type>(field.f_type.clone()).is_ok_and(|ty| is_option_type(&ty));
+    !serde_default && !is_option
+}
+
+/// 字段的 serde 属性中是否有 `default` 或 `default = "path"`
+fn has_serde_default(serde: &TokenStream) -> bool {
+    let Ok(attrs) = Parser::parse2(Attribute::parse_outer, serde.clone()) else {
+        return false;
+    };
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("serde"))
+        .filter_map(|attr| {
+            attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)
+                .ok()
+        })
+        .flatten()
+        .any(|meta| meta.path().is_ident("default"))
+}
+
+/// 字段的 schema：线上的类型、多值包装与校验规则
+fn field_schema(field: &FieldInfo) -> TokenStream {
+    let wire_type = field.try_from.as_ref().unwrap_or(&field.f_type);
+    let mut schema = match syn::parse2::<Type>(wire_type.clone()) {
+        Ok(ty) => type_schema(&ty),
+        Err(_) => quote! { ::mapping::openapi::Schema::default() },
+    };
+    if field.is_file {
+        schema = quote! { ::mapping::openapi::Schema::typed("string", Some("binary")) };
+    }
+    // 读入局部变量的多值字段只记录了元素类型
+    if field.is_vec && (field.is_local() || field.is_file) {
+        schema = quote! { ::mapping::openapi::Schema::array(#schema) };
+    }
+    let number = |bound: &Option<TokenStream>| match bound {
+        Some(bound) => quote! { ::mapping::openapi::Number::parse(&(#bound)) },
+        None => quote! { None },
+    };
+    let size = |bound: &Option<TokenStream>| match bound {
+        Some(bound) => quote! { Some(#bound) },
+        None => quote! { None },
+    };
+    let constraints = field
+        .validators
+        .iter()
+        .filter_map(|validator| match validator {
+            Validator::Range { min, max } => {
+                let (min, max) = (number(min), number(max));
+                Some(quote! { .range(#min, #max) })
+            }
+            Validator::Length { min, max } => {
+                let (min, max) = (size(min), size(max));
+                Some(quote! { .length(#min, #max) })
+            }
+            Validator::Regex(pattern) => Some(quote! { .pattern(#pattern) }),
+            Validator::Email => Some(quote! { .email() }),
+            Validator::Custom(_) => None,
+        });
+    quote! {
+        #schema
+            #(#constraints)*
+    }
+}
+
+/// 按类型名推断 schema，无法识别的类型（包括泛型参数）不加约束
+fn type_schema(ty: &Type) -> TokenStream {
+    let typed = |ty: &str, format: Option<&str>| {
+        let format = match format {
+            Some(format) => quote! { Some(#format) },
+            None => quote! { None },
+        };
+        quote! { ::mapping::openapi::Schema::typed(#ty, #format) }
+    };
+    match ty {
+        Type::Reference(reference) => type_schema(&reference.elem),
+        Type::Paren(paren) => type_schema(&paren.elem),
+        Type::Group(group) => type_schema(&group.elem),
+        Type::Slice(slice) => {
+            let items = type_schema(&slice.elem);
+            quote! { ::mapping::openapi::Schema::array(#items) }
+        }
+        Type::Array(array) => {
+            let items = type_schema(&array.elem);
+            quote! { ::mapping::openapi::Schema::array(#items) }
+        }
+        Type::Path(type_path) => {
+            let Some(segment) = type_path.path.segments.last() else {
+                return quote! { ::mapping::openapi::Schema::default() };
+            };
+            let args: Vec<&Type> = match &segment.arguments {
+                PathArguments::AngleBracketed(args) => args
+                    .args
+                    .iter()
+                    .filter_map(|arg| match arg {
+                        GenericArgument::Type(ty) => Some(ty),
+                        _ => None,
+                    })
+                    .collect(),
+                _ => Vec::new(),
+            };
+            let ident = segment.ident.to_string();
+            match (ident.as_str(), args.as_slice()) {
+                ("Option" | "Box" | "Arc" | "Rc" | "Cow" | "Sourced", [inner, ..]) => {
+                    type_schema(inner)
+                }
+                ("Vec" | "VecDeque" | "LinkedList", [inner, ..]) => {
+                    let items = type_schema(inner);
+                    quote! { ::mapping::openapi::Schema::array(#items) }
+                }
+                ("HashSet" | "BTreeSet", [inner, ..]) => {
+                    let items = type_schema(inner);
+                    quote! { ::mapping::openapi::Schema::set(#items) }
+                }
+                ("SmallVec", [Type::Array(array), ..]) => {
+                    let items = type_schema(&array.elem);
+                    quote! { ::mapping::openapi::Schema::array(#items) }
+                }
+                ("HashMap" | "BTreeMap", [_, value, ..]) => {
+                    let values = type_schema(value);
+                    quote! { ::mapping::openapi::Schema::map(#values) }
+                }
+                ("i8" | "i16" | "i32" | "u8" | "u16" | "u32", _)
+                | ("NonZeroI8" | "NonZeroI16" | "NonZeroI32", _)
+                | ("NonZeroU8" | "NonZeroU16" | "NonZeroU32", _) => typed("integer", Some("int32")),
+                ("i64" | "u64" | "i128" | "u128" | "isize" | "usize", _)
+                | ("NonZeroI64" | "NonZeroU64" | "NonZeroUsize", _) => {
+                    typed("integer", Some("int64"))
+                }
+                ("f32", _) => typed("number", Some("float")),
+                ("f64", _) => typed("number", Some("double")),
+                ("bool", _) => typed("boolean", None),
+                ("String" | "str" | "char" | "FastStr", _) => typed("string", None),
+                ("UploadFile" | "Bytes", _) => typed("string", Some("binary")),
+                _ => quote! { ::mapping::openapi::Schema::default() },
+            }
+        }
+        _ => quote! { ::mapping::openapi::Schema::default() },
+    }
+}
+
+/// flatten 字段的类型用到泛型参数时，要求其同样实现 `OpenApiSchema`
+fn openapi_bounds(generics: &Generics, fields: &[&FieldInfo]) -> Generics {
+    let mut generics = generics.clone();
+    let type_params: Vec<_> = generics
+        .type_params()
+        .map(|param| param.ident.to_string())
+        .collect();
+    if type_params.is_empty() {
+        return generics;
+    }
+    let where_clause = generics.make_where_clause();
+    for field in fields.iter().filter(|field| field.format == "flatten") {
+        let fty = &field.f_type;
+        if !super::mapping::uses_type_params(fty.to_token_stream(), &type_params) {
+            continue;
+        }
+        let predicates: Punctuated<WherePredicate, Token![,]> =
+            parse_quote!(#fty: ::mapping::OpenApiSchema);
+        where_clause.predicates.extend(predicates);
+    }
+    generics
+}
@@ -0,0 +1,135 @@
+use super::client::{body_expanded, client_bounds, write_expanded};
+use super::deserialize::{field_local_ident, local_ident};
+use super::field_attr::*;
+use super::helper::is_option_type;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{DeriveInput, Error, Fields, ext::IdentExt};
+
+// 可以写入响应的字段来源
+const RESPONSE_FORMATS: &[&str] = &["header", "cookie", "json", "form"];
+
+pub fn expand_into_response(input: &DeriveInput) -> Result<TokenStream, Error> {
+    let struct_name = &input.ident;
+    let default_format = get_default_format(&input.attrs)?;
+    let options = get_mapping_options(&input.attrs, &MappingOptions::default())?;
+    if options.flatten
+        || options.client
+        || options.path.is_some()
+        || options.accept.is_some()
+        || options.tag.is_some()
+        || options.rename.is_some()
+        || options.from.is_some()
+        || options.try_from.is_some()
+    {
+        return Err(Error::new_spanned(
+            struct_name,
+            "response structs only support source options such as `header(rename_all = \"...\")`",
+        ));
+    }
+    let syn::Data::Struct(data) = &input.data else {
+        return Err(Error::new_spanned(
+            struct_name,
+            "IntoResponseMapping can only be derived for structs",
+        ));
+    };
+    let Fields::Named(fields_named) = &data.fields else {
+        return Err(Error::new_spanned(
+            struct_name,
+            "IntoResponseMapping can only be derived for structs with named fields",
+        ));
+    };
+
+    let mut status = None;
+    let mut field_infos = Vec::new();
+    let mut body_format = None;
+    for field in &fields_named.named {
+        // `#[status]` 字段单独处理，不属于任何来源
+        if let Some(attr) = field
+            .attrs
+            .iter()
+            .find(|attr| attr.path().is_ident("status"))
+        {
+            if status.is_some() {
+                return Err(Error::new_spanned(attr, "duplicate #[status] field"));
+            }
+            status = Some(field);
+            continue;
+        }
+        let field_info = get_field_info(default_format.as_str(), &options, field)?;
+        if field_info.format == "flatten"
+            || field_info.format == "from"
+            || field_info.try_from.is_some()
+            || field_info.parse_with.is_some()
+            || !field_info.validators.is_empty()
+        {
+            return Err(Error::new_spanned(
+                field,
+                "`flatten`, `from`, `try_from`, `with` and #[validate] are not supported on response fields",
+            ));
+        }
+        let format = field_info.format.as_str();
+        if !RESPONSE_FORMATS.contains(&format) {
+            return Err(Error::new_spanned(
+                field,
+                format!(
+                    "#[{format}] fields cannot be written to a response, expected one of: status, {}",
+                    RESPONSE_FORMATS.join(", ")
+                ),
+            ));
+        }
+        if matches!(format, "json" | "form") {
+            match body_format {
+                Some(body_format) if body_format != format => {
+                    return Err(Error::new_spanned(
+                        field,
+                        "response structs can only write one body format",
+                    ));
+                }
+                _ => body_format = Some(field_info.format.clone()),
+            }
+        }
+        field_infos.push(field_info);
+    }
+
+    let generics = client_bounds(&input.generics, &field_infos);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let mut field_idents: Vec<_> = field_infos
+        .iter()
+        .map(|field| format_ident!("{}", field.name))
+        .collect();
+    let mut locals: Vec<_> = field_infos.iter().map(local_ident).collect();
+    let status_expanded = status.map(|field| {
+        let ident = field.ident.clone().unwrap();
+        let local = field_local_ident(&ident.unraw().to_string());
+        field_idents.push(ident);
+        locals.push(local.clone());
+        if is_option_type(&field.ty) {
+            quote! {
+                if let Some(__status) = #local {
+                    writer.status(__status);
+                }
+            }
+        } else {
+            quote! { writer.status(#local); }
+        }
+    });
+    let write_expanded = field_infos.iter().map(write_expanded);
+    let error = quote! { ::mapping::response::ResponseError };
+    let body_expanded =
+        body_format.map(|format| body_expanded(&format, &generics, &field_infos, &error));
+
+    Ok(quote! {
+        impl #impl_generics volo_http::server::IntoResponse for #struct_name #ty_generics #where_clause {
+            fn into_response(self) -> volo_http::response::Response {
+                let Self { #(#field_idents: #locals,)* } = self;
+                ::mapping::response::ResponseWriter::build(|writer| {
+                    #status_expanded
+                    #(#write_expanded)*
+                    #body_expanded
+                    Ok(())
+                })
+            }
+        }
+    })
+}
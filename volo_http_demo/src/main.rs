@@ -13,7 +13,7 @@ async fn test_handoer(req: TestParam) -> &'static str {
 }
 
 pub fn test_router() -> Router {
-    Router::new().merge(Router::new().route("/test/{pid}/{cid}/{items}/{cids}", get(test_handoer)))
+    Router::new().merge(Router::new().route(TestParam::PATH, get(test_handoer)))
 }
 
 fn timeout_handler(_: &ServerContext) -> (StatusCode, &'static str) {
@@ -191,17 +191,20 @@ use mapping::Mapping;
 //         Ok(res)
 //     }
 // }
+// 字段只在处理函数的 Debug 输出中使用
+#[allow(dead_code)]
 #[derive(Mapping, Default, Debug)]
+#[mapping(path = "/test/{pid}/{cid}/{items}/{cids}")]
 pub struct TestParam {
     #[header]
     #[serde(default, rename = "token")]
     token: Option<i64>,
-    #[header]
+    #[header(default)]
     ids: Vec<i64>,
     #[json]
     #[serde(default)]
     name: i64,
-    #[ext]
+    #[ext(default)]
     user_id: i64,
     #[query]
     #[serde(default)]